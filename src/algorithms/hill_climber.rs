@@ -1,36 +1,57 @@
 use crate::data_structures::{colors::Color, problem::Problem, solution::Solution};
+use crate::world::{
+    cell::{CellSum, CellValue},
+    Landscape,
+};
 use derive_more::Constructor;
-use rand::Rng;
 use std::{sync::Arc, thread, time::Duration};
 
-use super::Algorithm;
+use super::{random_step, Algorithm, Reach};
 
 #[derive(Constructor)]
-pub struct HillClimber {
-    problem: Problem,
-    solution: Arc<Solution>,
+pub struct HillClimber<T: CellValue = i64> {
+    problem: Problem<T>,
+    solution: Arc<Solution<T>>,
 }
 
 /// This struct implements the Hill Climber algorithm. It is a simple algorithm that moves in the direction of the highest
-/// value in the 3x3 neighborhood. If there is no higher value, it moves in a random direction. Additionally, 5% of the time,
-/// it moves in a random direction
-impl Algorithm for HillClimber {
+/// net value in the neighborhood given by the move model, being the value minus the cost of moving there. If there is no
+/// higher value, it moves in a random direction. Additionally, 5% of the time, it moves in a random direction
+impl<T: CellValue> Algorithm for HillClimber<T> {
+    fn name(&self) -> &'static str {
+        "Hill Climber"
+    }
+
     fn next_step(&mut self) {
-        let mut visited = Vec::<(i64, i64)>::with_capacity(self.problem.n_steps as usize);
-        let (mut at_x, mut at_y) = (self.problem.start_x, self.problem.start_y);
-        let mut score = 0;
-        let mut world = self.problem.world.clone();
+        let name = self.name();
+        // The agents collect from the same world, one after the other
+        let mut score = T::Sum::default();
+        let mut world = self.problem.attempt();
+        let mut paths = Vec::with_capacity(self.problem.agents.len());
+
+        for agent in &self.problem.agents {
+            let mut visited =
+                Vec::<(i64, i64)>::with_capacity(agent.n_steps.unwrap_or_default() as usize);
+            let (mut at_x, mut at_y) = (agent.start_x, agent.start_y);
+            let mut reach = Reach::new(&self.problem, agent);
+            let mut collected_by_agent = T::Sum::default();
 
-        for _ in 0..self.problem.n_steps {
-            // Store the current location and score
-            visited.push((at_y, at_x));
-            score += world.visit(at_y, at_x).unwrap();
+            for step in 0..agent.n_steps.unwrap_or(u64::MAX) {
+                // Store the current location and score
+                visited.push((at_y, at_x));
+                let collected = world.visit_by(at_y, at_x, step, name).unwrap();
+                reach.collect(collected);
+                collected_by_agent += collected.into();
 
-            // Find the maximum value in the 3x3 neighborhood
-            let (mut max_value, mut max_dy, mut max_dx) = (0, 0, 0);
-            for dy in -1..2 {
-                for dx in -1..2 {
-                    let value = world.at(at_y + dy, at_x + dx).unwrap_or(-1);
+                // Find the maximum net value in the neighborhood, being the value minus the cost of moving there
+                let (mut max_value, mut max_dy, mut max_dx) = (T::Sum::default(), 0, 0);
+                for &(dy, dx) in self.problem.move_model.moves(at_y) {
+                    let (y, x) = (at_y + dy, at_x + dx);
+                    if !world.is_passable(y, x) {
+                        continue;
+                    }
+                    let value = T::Sum::from(world.at(y, x).unwrap())
+                        - T::Sum::from_i64(self.problem.step_cost((at_y, at_x), (y, x)));
 
                     if max_value < value {
                         max_value = value;
@@ -38,35 +59,47 @@ impl Algorithm for HillClimber {
                         max_dx = dx;
                     }
                 }
-            }
 
-            // Take a random step 5% of the time
-            let mut take_random_step = rand::random_range(0. ..1.) < 0.05;
+                // Take a random step 5% of the time
+                let take_random_step = rand::random_range(0. ..1.) < 0.05;
 
-            // If we're not moving, then pick a random direction
-            while max_dy == 0 && max_dx == 0
-                || !world.in_world(at_y + max_dy, at_x + max_dx)
-                || take_random_step
-            {
-                take_random_step = false;
-                let mut rng = rand::rng();
-                max_dy = rng.random_range(-1..2);
-                max_dx = rng.random_range(-1..2);
-            }
+                // If we're not moving, then pick a random direction
+                if max_dy == 0 && max_dx == 0 || take_random_step {
+                    match random_step(&world, &self.problem.move_model, at_y, at_x) {
+                        Some((dy, dx)) => (max_dy, max_dx) = (dy, dx),
+                        // Surrounded by obstacles, no step can be taken
+                        None => break,
+                    }
+                }
+
+                // Keep the goal and a station within reach once the step is taken
+                let steps_left = agent.steps_left(step + 2);
+                match reach.step(&self.problem, (at_y, at_x), (max_dy, max_dx), steps_left) {
+                    Some((dy, dx)) => (max_dy, max_dx) = (dy, dx),
+                    // No step can be taken without giving up on the goal or the battery
+                    None => break,
+                }
+
+                // Move to the maximum value
+                at_x += max_dx;
+                at_y += max_dy;
 
-            // Move to the maximum value
-            at_x += max_dx;
-            at_y += max_dy;
+                // Share the path so far
+                self.solution
+                    .submit_path_in_progress(Color::YELLOW, &visited);
+                thread::sleep(Duration::from_millis(1));
+            }
 
-            // Store the current path
-            visited.push((at_y as i64, at_x as i64));
-            self.solution
-                .submit_path_in_progress(Color::YELLOW, &visited);
-            thread::sleep(Duration::from_millis(1));
+            // The objective is the net score, being the collected value minus the cost of travelling the paths
+            score += collected_by_agent - T::Sum::from_i64(self.problem.path_cost(&visited));
+            paths.push(visited);
         }
 
-        if self.solution.submit_path(&visited, score) {
-            tracing::info!("Hill Climber: New high score: {}", score);
+        if self
+            .solution
+            .submit_paths(&paths, score, world.get_journal())
+        {
+            tracing::info!("{}: New high score: {}", name, score);
         }
     }
 }
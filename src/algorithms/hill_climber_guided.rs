@@ -1,166 +1,165 @@
 use crate::data_structures::{colors::Color, problem::Problem, solution::Solution};
+use crate::world::{
+    attempt::AttemptWorld,
+    cell::{CellSum, CellValue},
+    Landscape,
+};
 use derive_more::Constructor;
-use ndarray::{s, Array2, ArrayView2, Axis};
+use ndarray::{Array2, Axis};
 use std::{sync::Arc, thread, time::Duration};
 
-use super::Algorithm;
+use super::{random_step, Algorithm, Reach};
 
 /// This struct is used to implement the Hill Climber Guided algorithm. It is a modified version of the Hill Climber algorithm.
-/// It searching in all direction within a certain range, and moves in the direction of a strong pull. If there is no strong pull,
-/// the search range is increased. If the search range is too large, the algorithm stops. At this point, the algorithm will walk
-/// in a random direction. Additionally, 5% of the time, it moves in a random direction
+/// It searches the blocks in all directions around the current location in a level of the pyramid of the world, and moves in
+/// the direction of a strong pull. If there is no strong pull, the next, coarser level is searched. If the blocks of the level
+/// are too large, the algorithm stops. At this point, the algorithm will walk in a random direction. Additionally, 5% of the
+/// time, it moves in a random direction
 #[derive(Constructor)]
-pub struct HillClimberGuided {
-    problem: Problem,
-    solution: Arc<Solution>,
+pub struct HillClimberGuided<T: CellValue = i64> {
+    problem: Problem<T>,
+    solution: Arc<Solution<T>>,
 }
 
-impl Algorithm for HillClimberGuided {
+impl<T: CellValue> Algorithm for HillClimberGuided<T> {
+    fn name(&self) -> &'static str {
+        "Hill Climber Guided"
+    }
+
     fn next_step(&mut self) {
-        let mut visited = Vec::<(i64, i64)>::with_capacity(self.problem.n_steps as usize);
-        let (mut at_x, mut at_y) = (self.problem.start_x, self.problem.start_y);
-
-        // Keep track of the current score
-        let mut score = 0;
-
-        // Run the algorithm for n_steps
-        for _ in 0..self.problem.n_steps {
-            // Store the current location and score
-            visited.push((at_y, at_x));
-            score += self.problem.world.visit(at_y, at_x).unwrap();
-
-            // Search granularity. This will keep growing
-            let mut search_granularity = 1;
-            // Search ranges matching the granularity. These will keep growing
-            let mut search_ranges = vec![-1, 0, 1];
-
-            // Loop until we find a pull towards a certain direction, or until its decided that no pull can be found
-            loop {
-                // Initialize a 3x3 grid that will store the values of the 9 blocks around the current location
-                let mut grid3x3: Array2<i64> = Array2::zeros((3, 3));
-
-                // Fill the 3x3 grid
-                for dy in 0..3 as i64 {
-                    for dx in 0..3 as i64 {
-                        // Skip the center. Not moving is not an option
-                        if dx == 1 && dy == 1 {
-                            continue;
+        let name = self.name();
+        // Keep track of the joint score. The agents collect from the same world, one after the other
+        let mut score = T::Sum::default();
+        let mut world = self.problem.attempt();
+        let mut paths = Vec::with_capacity(self.problem.agents.len());
+
+        for agent in &self.problem.agents {
+            let mut visited =
+                Vec::<(i64, i64)>::with_capacity(agent.n_steps.unwrap_or_default() as usize);
+            let (mut at_x, mut at_y) = (agent.start_x, agent.start_y);
+            let mut reach = Reach::new(&self.problem, agent);
+            let mut collected_by_agent = T::Sum::default();
+
+            // Run the algorithm for the n_steps of the agent, or until the agent can not take another step
+            'steps: for step in 0..agent.n_steps.unwrap_or(u64::MAX) {
+                // Store the current location and score
+                visited.push((at_y, at_x));
+                let collected = world.visit_by(at_y, at_x, step, name).unwrap();
+                reach.collect(collected);
+                collected_by_agent += collected.into();
+                let (from_y, from_x) = (at_y, at_x);
+
+                // Level of the pyramid of the world that is searched. Its locations cover blocks of 3^level by
+                // 3^level locations of the world. This will keep growing
+                let mut level = 0;
+
+                // Loop until we find a pull towards a certain direction, or until its decided that no pull can be found
+                loop {
+                    // Initialize a 3x3 grid that will store the values of the 9 blocks around the block of the current
+                    // location in the level
+                    let mut grid3x3: Array2<f64> = Array2::zeros((3, 3));
+                    let size = AttemptWorld::<T>::PYRAMID_FACTOR.pow(level as u32) as i64;
+                    let (block_y, block_x) = (at_y.div_euclid(size), at_x.div_euclid(size));
+
+                    // Fill the 3x3 grid
+                    for dy in 0..3_i64 {
+                        for dx in 0..3_i64 {
+                            // Skip the center. Not moving is not an option
+                            if dx == 1 && dy == 1 {
+                                continue;
+                            }
+
+                            // Store the summation of the block. Blocks (partially) out of bounds are clamped to the world
+                            grid3x3[[dy as usize, dx as usize]] = world
+                                .level_sum(level, block_y + dy - 1, block_x + dx - 1)
+                                .to_f64();
                         }
+                    }
 
-                        // Get the topleft corner of the block
-                        let y = at_y + search_ranges[dy as usize];
-                        let x = at_x + search_ranges[dx as usize];
-
-                        // Get the block (a submatrix of the world)
-                        let world_view = self.problem.world.get_information();
-                        // tracing::info!("y {}, y+S {}, x {}, x+S {}", y, y+S, x, x+S);
-                        let submatrix = get_submatrix(
-                            &world_view,
-                            y,
-                            y + search_granularity,
-                            x,
-                            x + search_granularity,
-                        );
-
-                        // If a block was found, store the summation of that block
-                        // (it might happen that the block is completely of bounds)
-                        if submatrix.is_some() {
-                            let submatrix = submatrix.unwrap();
-                            grid3x3[[dy as usize, dx as usize]] = submatrix.sum();
+                    /* Determine termination conditions */
+
+                    // Determine if there is a pull towards a certain direction
+                    let (step_taken, next_y, next_x) = match get_pull(grid3x3, 0.4) {
+                        // Pulls towards the center are not valid
+                        Some((0, 0)) => (false, 0, 0),
+                        // Pulls towards another direction are followed by the step of the move model that points most
+                        // closely towards it, as long as there is no obstacle in the way
+                        Some((dy, dx)) => {
+                            match self.problem.move_model.closest_move(at_y, dy, dx) {
+                                Some((dy, dx)) if world.is_passable(at_y + dy, at_x + dx) => {
+                                    (true, at_y + dy, at_x + dx)
+                                }
+                                _ => (false, 0, 0),
+                            }
                         }
+                        // No pull was found
+                        None => (false, 0, 0),
+                    };
+
+                    // Determine if the search is too large. In non-square worlds, the search can only be considered
+                    // too large once the blocks around the current location have outgrown the longest side of the world
+                    let (height, width) = (
+                        self.problem.world.get_height(),
+                        self.problem.world.get_width(),
+                    );
+                    let search_too_large = (usize::max(height, width) as i64) < size * 3;
+
+                    // Take a random step 5% of the time
+                    let take_random_step = rand::random_range(0. ..1.) < 0.05;
+
+                    // Search the next, coarser level
+                    level += 1;
+
+                    if (!step_taken && search_too_large) || take_random_step {
+                        // Search for a random step that can be taken
+                        match random_step(&world, &self.problem.move_model, at_y, at_x) {
+                            Some((dy, dx)) => {
+                                at_x += dx;
+                                at_y += dy;
+                            }
+                            // Surrounded by obstacles, no step can be taken
+                            None => break 'steps,
+                        }
+                        break;
+                    } else if step_taken {
+                        at_y = next_y;
+                        at_x = next_x;
+                        break;
                     }
                 }
 
-                /* Determine termination conditions */
-
-                // Determine if there is a pull towards a certain direction
-                let (step_taken, next_y, next_x) = match get_pull(grid3x3.mapv(|v| v as f64), 0.4) {
-                    // Pulls towards the center are not valid
-                    Some((0, 0)) => (false, 0, 0),
-                    // Pulls towards another direction are valid
-                    Some((dy, dx)) => (true, at_y + dy, at_x + dx),
-                    // No pull was found
-                    _ => (false, 0, 0),
-                };
-
-                // Determine if the search ranges are too large
-                let search_too_large = (self.problem.world.get_grid_size() as i64)
-                    < (search_ranges[1] - search_ranges[0]);
-
-                // Take a random step 5% of the time
-                let take_random_step = rand::random_range(0. ..1.) < 0.05;
-
-                // Update search ranges
-                search_ranges[0] -= search_granularity * 3;
-                search_ranges[1] -= search_granularity;
-                search_ranges[2] += search_granularity;
-                // Update search granularity
-                search_granularity *= 3;
-
-                if (!step_taken && search_too_large) || take_random_step {
-                    // Search for a random step that can be taken
-                    loop {
-                        let dx = rand::random_range(-1..2);
-                        let dy = rand::random_range(-1..2);
-                        if self.problem.world.in_world(at_y + dy, at_x + dx) {
-                            at_x += dx;
-                            at_y += dy;
-                            break;
-                        }
-                    }
-                    break;
-                } else if step_taken {
-                    at_y = next_y;
-                    at_x = next_x;
-                    break;
+                // Keep the goal and a station within reach once the step is taken
+                let steps_left = agent.steps_left(step + 2);
+                match reach.step(
+                    &self.problem,
+                    (from_y, from_x),
+                    (at_y - from_y, at_x - from_x),
+                    steps_left,
+                ) {
+                    Some((dy, dx)) => (at_y, at_x) = (from_y + dy, from_x + dx),
+                    // No step can be taken without giving up on the goal or the battery
+                    None => break,
                 }
+
+                // Share the path so far
+                self.solution.submit_path_in_progress(Color::CYAN, &visited);
+                thread::sleep(Duration::from_millis(1));
             }
 
-            // Store the current path
-            visited.push((at_y as i64, at_x as i64));
-            self.solution.submit_path_in_progress(Color::CYAN, &visited);
-            thread::sleep(Duration::from_millis(1));
+            // The objective is the net score, being the collected value minus the cost of travelling the paths
+            score += collected_by_agent - T::Sum::from_i64(self.problem.path_cost(&visited));
+            paths.push(visited);
         }
 
-        if self.solution.submit_path(&visited, score) {
-            tracing::info!("Hill Climber Guided: New high score: {}", score);
+        if self
+            .solution
+            .submit_paths(&paths, score, world.get_journal())
+        {
+            tracing::info!("{}: New high score: {}", name, score);
         }
     }
 }
 
-fn get_submatrix<'a>(
-    matrix: &'a ArrayView2<i64>,
-    start_row: i64,
-    end_row: i64,
-    start_col: i64,
-    end_col: i64,
-) -> Option<ArrayView2<'a, i64>> {
-    let rows = matrix.nrows() as i64;
-    let cols = matrix.ncols() as i64;
-
-    // Check if the submatrix is within bounds and valid
-    if rows < start_row
-        || cols < start_col
-        || end_row < 0
-        || end_col < 0
-        || end_row < start_row
-        || end_col < start_col
-    {
-        return None;
-    }
-
-    // Clamp the bounds to avoid out-of-bounds slicing
-    let start_row_clamped = start_row.max(0) as usize;
-    let start_col_clamped = start_col.max(0) as usize;
-    let end_row_clamped = end_row.min(rows) as usize;
-    let end_col_clamped = end_col.min(cols) as usize;
-
-    Some(matrix.slice(s![
-        start_row_clamped..end_row_clamped,
-        start_col_clamped..end_col_clamped
-    ]))
-}
-
 /// This function returns the direction of the pull, given that the center is pulled towards one of the directions around it
 /// A pull is only considered strong enough if the given threshold is reached. If no pull is strong enough, None is returned
 fn get_pull(grid: Array2<f64>, threshold: f64) -> Option<(i64, i64)> {
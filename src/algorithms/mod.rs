@@ -1,3 +1,6 @@
+use clap::ValueEnum;
+use rand::seq::IndexedRandom;
+use serde::{Deserialize, Serialize};
 use std::{
     sync::{
         atomic::{AtomicBool, Ordering},
@@ -7,10 +10,23 @@ use std::{
     time::Duration,
 };
 
+use crate::{
+    data_structures::{
+        energy::EnergyNeeds,
+        goal::GoalDistances,
+        move_model::MoveModel,
+        problem::{Agent, Problem},
+        solution::Solution,
+    },
+    world::{cell::CellValue, Landscape},
+};
+
 /// This trait is used to implement algorithms. The run function is used to continuously run the algorithm
 /// The initial_step function is used to initialize the algorithm if needed. The next_step function is used
-/// to improve upon any previously found solutions
+/// to improve upon any previously found solutions. The name is used in logging, and to record visits in the journal of the world
 pub trait Algorithm {
+    fn name(&self) -> &'static str;
+
     fn run(&mut self, stop_signal: Arc<AtomicBool>) {
         self.initial_step();
 
@@ -27,6 +43,304 @@ pub trait Algorithm {
     fn next_step(&mut self);
 }
 
+/// The algorithms that can be enabled for a problem
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "snake_case")]
+#[value(rename_all = "snake_case")]
+pub enum AlgorithmKind {
+    HillClimber,
+    RandomWalker,
+    HillClimberGuided,
+}
+
+impl AlgorithmKind {
+    pub const ALL: [AlgorithmKind; 3] = [
+        AlgorithmKind::HillClimber,
+        AlgorithmKind::RandomWalker,
+        AlgorithmKind::HillClimberGuided,
+    ];
+
+    /// Creates the algorithm, working on the given problem and submitting to the given solution
+    pub fn create<T: CellValue>(
+        self,
+        problem: Problem<T>,
+        solution: Arc<Solution<T>>,
+    ) -> Box<dyn Algorithm + Send> {
+        match self {
+            AlgorithmKind::HillClimber => Box::new(HillClimber::new(problem, solution)),
+            AlgorithmKind::RandomWalker => Box::new(RandomWalker::new(problem, solution)),
+            AlgorithmKind::HillClimberGuided => Box::new(HillClimberGuided::new(problem, solution)),
+        }
+    }
+}
+
+/// Returns a random step (dy, dx) of the move model from the given location to a location that can be moved to.
+/// Staying in place is never picked. Returns None if there is no such location
+pub fn random_step(
+    world: &impl Landscape,
+    move_model: &MoveModel,
+    y: i64,
+    x: i64,
+) -> Option<(i64, i64)> {
+    let steps: Vec<(i64, i64)> = move_model
+        .moves(y)
+        .iter()
+        .copied()
+        .filter(|&(dy, dx)| (dy, dx) != (0, 0) && world.is_passable(y + dy, x + dx))
+        .collect();
+    steps.choose(&mut rand::rng()).copied()
+}
+
+/// Keeps what an agent needs within reach while it walks: the goal of the problem, and a station to recharge its
+/// battery at. Tracks the energy left in the battery of the agent
+pub struct Reach<T: CellValue = i64> {
+    goal_distances: Option<Arc<GoalDistances<T>>>,
+    energy_needs: Option<Arc<EnergyNeeds<T>>>,
+    energy: i64,
+    /// Whether the agent has a limit on its steps
+    limited: bool,
+    /// Whether the agent has collected any value since it last recharged its battery
+    collected: bool,
+}
+
+impl<T: CellValue> Reach<T> {
+    /// Starts the walk of an agent, with a full battery
+    pub fn new(problem: &Problem<T>, agent: &Agent) -> Reach<T> {
+        Reach {
+            goal_distances: problem.goal_distances(agent),
+            energy_needs: problem.energy_needs(),
+            energy: problem.energy.as_ref().map_or(0, |energy| energy.capacity),
+            limited: agent.n_steps.is_some(),
+            collected: false,
+        }
+    }
+
+    /// Records the value collected by a visit of the agent
+    pub fn collect(&mut self, value: T) {
+        self.collected |= T::default() < value;
+    }
+
+    /// Decides the step that is taken from a location. The step (dy, dx) is taken if the goal can still be reached
+    /// with the steps left after it, and a station with the energy left after it. Otherwise, the step that brings the
+    /// agent closest to the goal and then to a station is taken instead. If no step keeps both within reach, the goal
+    /// is kept within reach at the expense of the station. Returns None if no step can be taken without giving up on
+    /// the goal or draining the battery. The energy used by the step is taken from the battery. An agent without a
+    /// limit on its steps could keep recharging forever, so it does not return to a station without having collected
+    /// anything since its last recharge, and its path ends instead
+    pub fn step(
+        &mut self,
+        problem: &Problem<T>,
+        (y, x): (i64, i64),
+        (dy, dx): (i64, i64),
+        steps_left: u64,
+    ) -> Option<(i64, i64)> {
+        let step = [true, false].into_iter().find_map(|keep_station| {
+            if self.allows(problem, (y, x), (dy, dx), steps_left, keep_station) {
+                return Some((dy, dx));
+            }
+            problem
+                .move_model
+                .moves(y)
+                .iter()
+                .copied()
+                .filter(|&(dy, dx)| {
+                    (dy, dx) != (0, 0)
+                        && problem.world.is_passable(y + dy, x + dx)
+                        && self.allows(problem, (y, x), (dy, dx), steps_left, keep_station)
+                })
+                .min_by_key(|&(dy, dx)| {
+                    let distance = self
+                        .goal_distances
+                        .as_ref()
+                        .and_then(|goal_distances| goal_distances.distance(y + dy, x + dx));
+                    let need = self
+                        .energy_needs
+                        .as_ref()
+                        .and_then(|energy_needs| energy_needs.need(y + dy, x + dx));
+                    (distance, need)
+                })
+        })?;
+        if let Some(energy) = &problem.energy {
+            if energy.is_station(&problem.world, y + step.0, x + step.1) {
+                if !self.limited && !self.collected {
+                    return None;
+                }
+                self.collected = false;
+            }
+            self.energy = energy
+                .spend(problem, self.energy, (y, x), (y + step.0, x + step.1))
+                .unwrap_or(0);
+        }
+        Some(step)
+    }
+
+    /// Returns whether the goal, and if keep_station is set a station, can still be reached after stepping (dy, dx)
+    /// from a location
+    fn allows(
+        &self,
+        problem: &Problem<T>,
+        (y, x): (i64, i64),
+        (dy, dx): (i64, i64),
+        steps_left: u64,
+        keep_station: bool,
+    ) -> bool {
+        let (to_y, to_x) = (y + dy, x + dx);
+        // The battery can not be drained below zero
+        let left = match &problem.energy {
+            Some(energy) => match energy.spend(problem, self.energy, (y, x), (to_y, to_x)) {
+                Some(left) => left,
+                None => return false,
+            },
+            None => 0,
+        };
+        // The way to the goal has to remain within reach, which also keeps the battery from running out on the way.
+        // It may lead along the closest station
+        let goal_in_reach = self.goal_distances.as_ref().is_none_or(|goal_distances| {
+            goal_distances.reachable(
+                problem,
+                self.energy_needs.as_deref(),
+                (to_y, to_x),
+                steps_left,
+                left,
+            )
+        });
+        // If no station was within reach to begin with, any step the battery allows is fine
+        let station_in_reach = match &self.energy_needs {
+            Some(energy_needs) if keep_station => {
+                let in_reach =
+                    |need: Option<i64>, energy: i64| need.is_some_and(|need| need <= energy);
+                in_reach(energy_needs.need(to_y, to_x), left)
+                    || !in_reach(energy_needs.need(y, x), self.energy)
+            }
+            _ => true,
+        };
+        goal_in_reach && station_in_reach
+    }
+}
+
 pub mod hill_climber;
 pub mod hill_climber_guided;
 pub mod random_walker;
+use hill_climber::HillClimber;
+use hill_climber_guided::HillClimberGuided;
+use random_walker::RandomWalker;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        data_structures::{
+            energy::EnergyModel,
+            goal::Goal,
+            problem::MoveCost,
+            problem_spec::{CellType, ProblemSpec, WorldSource, WorldSpec},
+        },
+        world::World,
+    };
+
+    /// Runs a few attempts of every algorithm on the problem, and returns the best paths each of them found
+    fn best_paths<T: CellValue>(problem: &Problem<T>) -> Vec<Vec<Vec<(i64, i64)>>> {
+        AlgorithmKind::ALL
+            .iter()
+            .map(|kind| {
+                let solution = Arc::new(Solution::new(problem.clone()));
+                let mut algorithm = kind.create(problem.clone(), solution.clone());
+                algorithm.initial_step();
+                for _ in 0..3 {
+                    algorithm.next_step();
+                }
+                let paths = solution.paths.lock().unwrap().clone();
+                assert!(!paths.is_empty(), "{:?} found no paths", kind);
+                paths
+            })
+            .collect()
+    }
+
+    fn problem(goal: Goal) -> Problem {
+        let world = Arc::new(World::random(20, 20, 0.2, 1));
+        Problem::with_agents(world, 0, vec![Agent::new(5, 5, Some(25))], 1).with_goal(Some(goal))
+    }
+
+    #[test]
+    fn a_closed_tour_returns_to_the_start() {
+        for paths in best_paths(&problem(Goal::Start)) {
+            assert_eq!(paths[0].first(), Some(&(5, 5)));
+            assert_eq!(paths[0].last(), Some(&(5, 5)));
+        }
+    }
+
+    #[test]
+    fn a_goal_region_is_entered_by_the_last_step() {
+        let goal = Goal::Region {
+            y0: 14,
+            y1: 17,
+            x0: 12,
+            x1: 15,
+        };
+        let in_region = |&(y, x): &(i64, i64)| (14..17).contains(&y) && (12..15).contains(&x);
+        for paths in best_paths(&problem(goal)) {
+            // The paths start outside of the region, and end inside it
+            assert!(!in_region(&paths[0][0]));
+            assert!(in_region(paths[0].last().unwrap()), "{:?}", paths[0]);
+        }
+    }
+
+    /// A problem in which the battery of the agent is the only limit on its path
+    fn battery_only(stations: Vec<(i64, i64)>) -> Problem {
+        let world = Arc::new(World::random(20, 20, 0.2, 1));
+        let energy = EnergyModel {
+            capacity: 20,
+            move_energy: MoveCost {
+                orthogonal: 2,
+                diagonal: 3,
+            },
+            stations,
+        };
+        Problem::with_agents(world, 0, vec![Agent::new(5, 5, None)], 1).with_energy(Some(energy))
+    }
+
+    #[test]
+    fn a_battery_only_agent_stops_when_its_battery_runs_out() {
+        // Every step uses at least 2 of the 20 energy, so at most 10 steps are taken
+        for paths in best_paths(&battery_only(vec![])) {
+            assert!(1 < paths[0].len() && paths[0].len() <= 11, "{:?}", paths[0]);
+        }
+    }
+
+    #[test]
+    fn a_battery_only_agent_recharges_at_a_station() {
+        // Every path is accepted, so the battery never ran out. A path longer than 11 locations was only possible by
+        // recharging on the way
+        let lengths: Vec<usize> = best_paths(&battery_only(vec![(5, 5), (8, 8)]))
+            .iter()
+            .map(|paths| paths[0].len())
+            .collect();
+        assert!(lengths.iter().any(|&length| 11 < length), "{:?}", lengths);
+    }
+
+    #[test]
+    fn a_world_of_fractional_values_is_searched() {
+        let spec = ProblemSpec {
+            world: WorldSpec {
+                source: WorldSource::Perlin {
+                    height: 30,
+                    width: 30,
+                    sparsity: 0.3,
+                    seed: None,
+                },
+                cell_type: CellType::F64,
+                ..ProblemSpec::default().world
+            },
+            n_steps: Some(40),
+            seed: Some(2),
+            ..ProblemSpec::default()
+        };
+        let problem = spec.build::<f64>().unwrap();
+        assert_eq!(problem.world.get_bounds(), (0.0, 1.0));
+        // Every path is accepted, so the scores the algorithms summed match the scores of the problem exactly
+        for paths in best_paths(&problem) {
+            assert!(0.0 < problem.score_paths(&paths));
+            assert!(problem.score_paths(&paths).fract() != 0.0);
+        }
+    }
+}
@@ -2,49 +2,74 @@ use derive_more::Constructor;
 use std::{sync::Arc, thread, time::Duration};
 
 use crate::data_structures::{colors::Color, problem::Problem, solution::Solution};
+use crate::world::cell::{CellSum, CellValue};
 
-use super::Algorithm;
+use super::{random_step, Algorithm, Reach};
 
 #[derive(Constructor)]
-pub struct RandomWalker {
-    problem: Problem,
-    solution: Arc<Solution>,
+pub struct RandomWalker<T: CellValue = i64> {
+    problem: Problem<T>,
+    solution: Arc<Solution<T>>,
 }
 
-impl Algorithm for RandomWalker {
+impl<T: CellValue> Algorithm for RandomWalker<T> {
+    fn name(&self) -> &'static str {
+        "Random Walker"
+    }
+
     fn initial_step(&mut self) {
         self.next_step();
     }
 
     fn next_step(&mut self) {
-        let mut visited = Vec::<(i64, i64)>::with_capacity(self.problem.n_steps as usize);
-        let (mut at_x, mut at_y) = (self.problem.start_x, self.problem.start_y);
-        let mut score = 0;
-        let mut world = self.problem.world.clone();
-
-        for _ in 0..self.problem.n_steps {
-            visited.push((at_y, at_x));
-            score += world.visit(at_y, at_x).unwrap();
-
-            loop {
-                let dx = rand::random_range(-1..2);
-                let dy = rand::random_range(-1..2);
-                if world.in_world(at_y + dy, at_x + dx) {
-                    at_x += dx;
-                    at_y += dy;
-                    break;
+        let name = self.name();
+        // The agents collect from the same world, one after the other
+        let mut score = T::Sum::default();
+        let mut world = self.problem.attempt();
+        let mut paths = Vec::with_capacity(self.problem.agents.len());
+
+        for agent in &self.problem.agents {
+            let mut visited =
+                Vec::<(i64, i64)>::with_capacity(agent.n_steps.unwrap_or_default() as usize);
+            let (mut at_x, mut at_y) = (agent.start_x, agent.start_y);
+            let mut reach = Reach::new(&self.problem, agent);
+            let mut collected_by_agent = T::Sum::default();
+
+            for step in 0..agent.n_steps.unwrap_or(u64::MAX) {
+                visited.push((at_y, at_x));
+                let collected = world.visit_by(at_y, at_x, step, name).unwrap();
+                reach.collect(collected);
+                collected_by_agent += collected.into();
+
+                // Keep the goal and a station within reach once the step is taken
+                let steps_left = agent.steps_left(step + 2);
+                let step_taken = random_step(&world, &self.problem.move_model, at_y, at_x)
+                    .and_then(|step| reach.step(&self.problem, (at_y, at_x), step, steps_left));
+                match step_taken {
+                    Some((dy, dx)) => {
+                        at_x += dx;
+                        at_y += dy;
+                    }
+                    // Surrounded by obstacles or out of steps or energy, no step can be taken
+                    None => break,
                 }
+
+                // Share the path so far
+                self.solution
+                    .submit_path_in_progress(Color::MAGENTA, &visited);
+                thread::sleep(Duration::from_millis(1));
             }
 
-            // Store the current path
-            visited.push((at_y as i64, at_x as i64));
-            self.solution
-                .submit_path_in_progress(Color::MAGENTA, &visited);
-            thread::sleep(Duration::from_millis(1));
+            // The objective is the net score, being the collected value minus the cost of travelling the paths
+            score += collected_by_agent - T::Sum::from_i64(self.problem.path_cost(&visited));
+            paths.push(visited);
         }
 
-        if self.solution.submit_path(&visited, score) {
-            tracing::info!("Random Walker: New high score: {}", score);
+        if self
+            .solution
+            .submit_paths(&paths, score, world.get_journal())
+        {
+            tracing::info!("{}: New high score: {}", name, score);
         }
     }
 }
@@ -0,0 +1,494 @@
+use std::{
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use clap::{Args, Parser, Subcommand};
+use color_eyre::eyre::{bail, eyre, Result};
+
+use crate::{
+    algorithms::AlgorithmKind,
+    data_structures::{
+        problem::Problem,
+        problem_spec::{AgentSpec, CellType, ProblemSpec, WorldSource},
+        solution::{SavedSolution, Solution},
+    },
+    visualisation::window::MyWindow,
+    world::{cell::CellValue, journal::Journal},
+};
+
+/// Searches a world for the path of n steps with the highest score, within a time budget. Without a command, the
+/// arguments of run are accepted, and a problem is run
+#[derive(Parser)]
+#[command(name = "avlr", version, args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub run: RunArgs,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Solves a problem. The search is shown in a window, unless headless
+    Run(RunArgs),
+    /// Generates the world of a problem, and writes it to a world file
+    Generate(GenerateArgs),
+    /// Solves every problem of a suite headless, and reports the score of each
+    Bench(BenchArgs),
+    /// Shows a saved solution in a window
+    Replay(ReplayArgs),
+    /// Validates the path of a saved solution, and computes its score
+    Score(ScoreArgs),
+}
+
+/// Selects a problem, and overrides parts of it
+#[derive(Args, Default)]
+pub struct ProblemArgs {
+    /// Name of a problem in the problems directory, or the path of a problem file. The default problem if left out
+    #[arg(env = "AVLR_PROBLEM")]
+    pub problem: Option<String>,
+    /// Seed from which the world and the start location are generated. Set it to replay a run
+    #[arg(long, env = "AVLR_SEED")]
+    pub seed: Option<u64>,
+    /// World file to use instead of the world of the problem
+    #[arg(long, env = "AVLR_WORLD")]
+    pub world: Option<PathBuf>,
+    /// Time budget in milliseconds
+    #[arg(long)]
+    pub milliseconds: Option<u64>,
+    /// Number of steps of a path. Without it, paths are limited by the battery of the agents, if they have one
+    #[arg(long)]
+    pub n_steps: Option<u64>,
+    /// Start location as y,x
+    #[arg(long, value_parser = parse_location)]
+    pub start: Option<(i64, i64)>,
+    /// Number of agents, replacing the agents of the problem. Each agent walks n steps from its own random start
+    /// location, unless a start location is given
+    #[arg(long)]
+    pub agents: Option<usize>,
+    /// Algorithms to run, separated by commas
+    #[arg(long, value_delimiter = ',')]
+    pub algorithms: Option<Vec<AlgorithmKind>>,
+}
+
+#[derive(Args)]
+pub struct RunArgs {
+    #[command(flatten)]
+    pub problem: ProblemArgs,
+    /// Do not show the search in a window
+    #[arg(long)]
+    pub headless: bool,
+    /// Writes the solution to a TOML or JSON file
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+    /// Writes the visits of the attempt that found the solution to a CSV journal file
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct GenerateArgs {
+    /// Name of a problem in the problems directory, or the path of a problem file. The default problem if left out
+    pub problem: Option<String>,
+    /// Seed from which the world is generated, if it has no seed of its own
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// World file to write. The format is derived from the file extension
+    #[arg(long)]
+    pub output: PathBuf,
+}
+
+#[derive(Args)]
+pub struct BenchArgs {
+    /// Names or paths of the problems to solve. All problems in the problems directory if left out
+    pub problems: Vec<String>,
+    /// Seed for every problem, overriding the seeds of the problems
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Time budget in milliseconds for every problem, overriding the time budgets of the problems
+    #[arg(long)]
+    pub milliseconds: Option<u64>,
+    /// Algorithms to run on every problem, separated by commas
+    #[arg(long, value_delimiter = ',')]
+    pub algorithms: Option<Vec<AlgorithmKind>>,
+    /// Writes the results to a CSV file
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Solution file, as written by run
+    pub solution: PathBuf,
+    /// Journal file of the solution, as written by run. The visits in it are applied to the world
+    #[arg(long)]
+    pub journal: Option<PathBuf>,
+    /// Shows the world as it was at this timestep of the journal, instead of after all of its visits
+    #[arg(long, requires = "journal")]
+    pub timestep: Option<u64>,
+}
+
+#[derive(Args)]
+pub struct ScoreArgs {
+    /// Solution file, as written by run
+    pub solution: PathBuf,
+}
+
+impl Command {
+    pub fn execute(self) -> Result<()> {
+        match self {
+            Command::Run(args) => run(args),
+            Command::Generate(args) => generate(args),
+            Command::Bench(args) => bench(args),
+            Command::Replay(args) => replay(args),
+            Command::Score(args) => score(args),
+        }
+    }
+}
+
+impl ProblemArgs {
+    /// Loads the selected problem, and applies the overrides to it
+    pub fn spec(&self) -> Result<ProblemSpec> {
+        let mut spec = match &self.problem {
+            Some(name) => ProblemSpec::find(name)?,
+            None => ProblemSpec::default(),
+        };
+        if let Some(path) = &self.world {
+            spec.world.source = WorldSource::File { path: path.clone() };
+        }
+        spec.seed = self.seed.or(spec.seed);
+        spec.milliseconds = self.milliseconds.unwrap_or(spec.milliseconds);
+        spec.n_steps = self.n_steps.or(spec.n_steps);
+        spec.start = self.start.or(spec.start);
+        if let Some(agents) = self.agents {
+            spec.agents = vec![AgentSpec::default(); agents];
+        }
+        if let Some(algorithms) = &self.algorithms {
+            spec.algorithms = algorithms.clone();
+        }
+        Ok(spec)
+    }
+}
+
+/// Solves a problem, and optionally writes the solution to a file
+fn run(args: RunArgs) -> Result<()> {
+    let spec = args.problem.spec()?;
+    match spec.world.cell_type {
+        CellType::I64 => run_problem::<i64>(&args, &spec),
+        CellType::F64 => run_problem::<f64>(&args, &spec),
+    }
+}
+
+/// Solves a problem whose world holds values of type T
+fn run_problem<T: CellValue>(args: &RunArgs, spec: &ProblemSpec) -> Result<()> {
+    let problem = spec.build::<T>()?;
+    log_problem(&problem);
+    let solution = Arc::new(Solution::new(problem.clone()));
+
+    // Create visualizer
+    let visualizer_thread =
+        (!args.headless).then(|| MyWindow::new(problem.clone(), solution.clone()).run(60));
+
+    solve(&problem, &spec.algorithms, &solution);
+
+    if let Some(output) = &args.output {
+        SavedSolution::new(spec, &solution).save(output)?;
+        tracing::info!("Solution written to {}", output.display());
+    }
+    if let Some(journal) = &args.journal {
+        solution.journal.lock().unwrap().save(journal)?;
+        tracing::info!("Journal written to {}", journal.display());
+    }
+
+    // Keep showing the solution until the window is closed
+    if let Some(visualizer_thread) = visualizer_thread {
+        visualizer_thread
+            .join()
+            .map_err(|_| eyre!("Visualizer thread failed to join."))?;
+    }
+    Ok(())
+}
+
+/// Generates the world of a problem, and writes it to a world file. World files hold i64 values, so the world is
+/// generated as such, whatever the cell type of the problem
+fn generate(args: GenerateArgs) -> Result<()> {
+    let spec = match &args.problem {
+        Some(name) => ProblemSpec::find(name)?,
+        None => ProblemSpec::default(),
+    };
+    let seed = args.seed.or(spec.seed).unwrap_or_else(rand::random);
+    let world = spec.world.build::<i64>(seed)?;
+    world.save(&args.output)?;
+    tracing::info!(
+        "Generated world with seed {} written to {}",
+        seed,
+        args.output.display()
+    );
+    Ok(())
+}
+
+/// Solves every problem of a suite one after the other, and reports the score of each
+fn bench(args: BenchArgs) -> Result<()> {
+    let names = match args.problems.is_empty() {
+        true => ProblemSpec::names()?,
+        false => args.problems.clone(),
+    };
+    if names.is_empty() {
+        bail!("No problems found in {}", ProblemSpec::DIRECTORY);
+    }
+
+    let mut rows = vec![
+        "problem,seed,milliseconds,agents,score,path_length,total_value,reachable_value,sparsity,mean,std"
+            .to_string(),
+    ];
+    for name in names {
+        let spec = ProblemArgs {
+            problem: Some(name.clone()),
+            seed: args.seed,
+            milliseconds: args.milliseconds,
+            algorithms: args.algorithms.clone(),
+            ..ProblemArgs::default()
+        }
+        .spec()?;
+        rows.push(match spec.world.cell_type {
+            CellType::I64 => bench_problem::<i64>(&name, &spec)?,
+            CellType::F64 => bench_problem::<f64>(&name, &spec)?,
+        });
+    }
+
+    if let Some(output) = &args.output {
+        fs::write(output, rows.join("\n") + "\n")
+            .map_err(|e| eyre!("Could not write {}: {}", output.display(), e))?;
+        tracing::info!("Results written to {}", output.display());
+    }
+    Ok(())
+}
+
+/// Solves a problem of a suite whose world holds values of type T, and returns its row of results
+fn bench_problem<T: CellValue>(name: &str, spec: &ProblemSpec) -> Result<String> {
+    let problem = spec.build::<T>()?;
+    log_problem(&problem);
+
+    let solution = Arc::new(Solution::new(problem.clone()));
+    solve(&problem, &spec.algorithms, &solution);
+
+    // The total number of locations visited by all agents
+    let path_length: usize = solution.paths.lock().unwrap().iter().map(Vec::len).sum();
+    println!(
+        "{}: score={}, steps={}, seed={}",
+        name,
+        solution.get_score(),
+        path_length,
+        problem.seed
+    );
+    // The value of the world and the value within reach of the agents put the score in perspective
+    let stats = problem.world.stats(0);
+    Ok(format!(
+        "{},{},{},{},{},{},{},{},{},{},{}",
+        name,
+        problem.seed,
+        problem.milliseconds,
+        problem.agents.len(),
+        solution.get_score(),
+        path_length,
+        stats.total_value,
+        problem.reachable_value(&problem.agents),
+        stats.sparsity(),
+        stats.mean,
+        stats.std
+    ))
+}
+
+/// Shows a saved solution in a window, until the window is closed. With a journal, the world is shown as the visits
+/// in it left it at a timestep
+fn replay(args: ReplayArgs) -> Result<()> {
+    match SavedSolution::cell_type(&args.solution)? {
+        CellType::I64 => replay_solution::<i64>(&args),
+        CellType::F64 => replay_solution::<f64>(&args),
+    }
+}
+
+/// Shows a saved solution of a problem whose world holds values of type T
+fn replay_solution<T: CellValue>(args: &ReplayArgs) -> Result<()> {
+    let saved = SavedSolution::<T::Sum>::load(&args.solution)?;
+    let problem = saved.problem.build::<T>()?;
+    log_problem(&problem);
+
+    let journal = match &args.journal {
+        Some(path) => Journal::load(path)?,
+        None => Journal::default(),
+    };
+    let solution = Arc::new(Solution::new(problem.clone()));
+    if !solution.submit_paths(&saved.paths, saved.score, &journal) {
+        bail!("The solution does not solve its problem, see the score command for details");
+    }
+
+    // The solution is checked against the world as it was generated, and the window shows the visited world
+    let mut shown = problem.clone();
+    if let Some(path) = &args.journal {
+        if journal.is_empty() {
+            bail!("{} holds no visits", path.display());
+        }
+        if problem.regeneration.is_some() {
+            bail!("Journals of regenerating worlds can not be replayed, as their values change over time");
+        }
+        let mut world = (*problem.world).clone();
+        world.apply(&journal)?;
+        let end = journal
+            .entries()
+            .iter()
+            .map(|entry| entry.t)
+            .max()
+            .unwrap_or(0);
+        let t = args.timestep.unwrap_or(end).min(end);
+        tracing::info!(
+            "Journal of {} visits up to timestep {}. Showing timestep {}, after which {} locations change",
+            journal.len(),
+            end,
+            t,
+            world.diff(t, end).len()
+        );
+        shown.world = Arc::new(world.at_timestep(t));
+    }
+    MyWindow::new(shown, solution)
+        .run(60)
+        .join()
+        .map_err(|_| eyre!("Visualizer thread failed to join."))
+}
+
+/// Validates the paths of a saved solution against its problem, and computes their joint score
+fn score(args: ScoreArgs) -> Result<()> {
+    match SavedSolution::cell_type(&args.solution)? {
+        CellType::I64 => score_solution::<i64>(&args),
+        CellType::F64 => score_solution::<f64>(&args),
+    }
+}
+
+/// Validates a saved solution of a problem whose world holds values of type T
+fn score_solution<T: CellValue>(args: &ScoreArgs) -> Result<()> {
+    let saved = SavedSolution::<T::Sum>::load(&args.solution)?;
+    let problem = saved.problem.build::<T>()?;
+
+    if let Err(error) = problem.validate_paths(&saved.paths) {
+        bail!("Invalid path: {}", error);
+    }
+
+    let score = problem.score_paths(&saved.paths);
+    println!(
+        "score={}, recorded={}, agents={}, steps={}, cost={}",
+        score,
+        saved.score,
+        saved.paths.len(),
+        saved.paths.iter().map(Vec::len).sum::<usize>(),
+        saved
+            .paths
+            .iter()
+            .map(|path| problem.path_cost(path))
+            .sum::<i64>()
+    );
+    if score != saved.score {
+        bail!(
+            "The recorded score {} does not match the actual score {}",
+            saved.score,
+            score
+        );
+    }
+    Ok(())
+}
+
+/// Runs the algorithms on the problem until the time budget has passed. The best paths found are stored in the solution
+fn solve<T: CellValue>(
+    problem: &Problem<T>,
+    algorithms: &[AlgorithmKind],
+    solution: &Arc<Solution<T>>,
+) {
+    // Shared stop signal between threads
+    let stop_signal = Arc::new(AtomicBool::new(false));
+
+    // Start a thread for every enabled algorithm
+    let algorithm_threads: Vec<_> = algorithms
+        .iter()
+        .map(|kind| {
+            let mut algorithm = kind.create(problem.clone(), solution.clone());
+            let stop_signal_copy = Arc::clone(&stop_signal);
+            thread::spawn(move || {
+                algorithm.run(stop_signal_copy);
+            })
+        })
+        .collect();
+
+    // Timeout after T milliseconds
+    thread::sleep(Duration::from_millis(problem.milliseconds));
+    tracing::info!("Timeout reached! Stopping worker threads.");
+
+    // Signal the worker threads to stop, and wait for them to finish
+    stop_signal.store(true, Ordering::Relaxed);
+    for algorithm_thread in algorithm_threads {
+        algorithm_thread
+            .join()
+            .expect("Algorithm thread failed to join.");
+    }
+
+    tracing::info!(
+        "Final path collected by main thread with score {}",
+        solution.get_score()
+    );
+}
+
+fn log_problem<T: CellValue>(problem: &Problem<T>) {
+    let world = &problem.world;
+    tracing::info!(
+        "Problem created. {}x{} world, T={}ms, {} agents, seed={}",
+        world.get_height(),
+        world.get_width(),
+        problem.milliseconds,
+        problem.agents.len(),
+        problem.seed,
+    );
+    for (i, agent) in problem.agents.iter().enumerate() {
+        tracing::info!(
+            "Agent {}: n={} steps, y={}, x={}, reachable={}",
+            i,
+            agent
+                .n_steps
+                .map_or("unlimited".to_string(), |n_steps| n_steps.to_string()),
+            agent.start_y,
+            agent.start_x,
+            problem.reachable_value(std::slice::from_ref(agent)),
+        );
+    }
+    tracing::info!("World stats: {}", world.stats(1));
+    let islands = world.islands(T::default());
+    if let Some(island) = islands.by_value().first() {
+        tracing::info!(
+            "Islands: {}. The most valuable holds {} in {} locations around ({:.0}, {:.0}){}",
+            islands.islands().len(),
+            island.value,
+            island.area,
+            island.centroid.0,
+            island.centroid.1,
+            islands
+                .closest(island.label)
+                .map_or(String::new(), |(_, distance)| format!(
+                    ", {} steps from the closest other island",
+                    distance
+                )),
+        );
+    }
+}
+
+/// Parses a location written as y,x
+fn parse_location(text: &str) -> Result<(i64, i64), String> {
+    let (y, x) = text
+        .split_once(',')
+        .ok_or_else(|| format!("expected y,x but got {}", text))?;
+    let parse = |value: &str| value.trim().parse::<i64>().map_err(|e| e.to_string());
+    Ok((parse(y)?, parse(x)?))
+}
@@ -11,4 +11,15 @@ impl Color {
     pub const MAGENTA: Color = Color(0xFF00FF);
     pub const WHITE: Color = Color(0xFFFFFF);
     pub const BLACK: Color = Color(0x000000);
+    pub const DARK_RED: Color = Color(0x800000);
+    pub const ORANGE: Color = Color(0xFF8000);
+    pub const LIME: Color = Color(0x80FF00);
+    pub const PINK: Color = Color(0xFF80C0);
+    pub const TEAL: Color = Color(0x008080);
+
+    /// Colors used to tell islands apart
+    pub const ISLANDS: [Color; 4] = [Color::ORANGE, Color::LIME, Color::PINK, Color::TEAL];
+
+    /// Colors used to tell the paths of agents apart
+    pub const AGENTS: [Color; 4] = [Color::BLUE, Color::ORANGE, Color::PINK, Color::TEAL];
 }
@@ -0,0 +1,219 @@
+use std::{cmp::Reverse, collections::BinaryHeap, sync::Arc};
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use super::problem::{MoveCost, Problem};
+use crate::world::{cell::CellValue, Landscape, World};
+
+/// The battery of the agents. Every step uses energy, depending on its direction and the cost of the location moved
+/// onto, and the battery is recharged to its capacity at stations. The energy of an agent may never drop below zero
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EnergyModel {
+    /// Energy of a full battery. Agents start with a full battery
+    pub capacity: i64,
+    /// Energy used by orthogonal and diagonal steps, on top of the cost of the location moved onto
+    pub move_energy: MoveCost,
+    /// Locations (y, x) at which the battery is recharged to its capacity
+    #[serde(default)]
+    pub stations: Vec<(i64, i64)>,
+}
+
+impl EnergyModel {
+    /// Returns the energy used by stepping from one location to another. Staying in place uses no energy
+    pub fn step_energy<T: CellValue>(
+        &self,
+        problem: &Problem<T>,
+        (from_y, from_x): (i64, i64),
+        (to_y, to_x): (i64, i64),
+    ) -> i64 {
+        let (dy, dx) = (to_y - from_y, to_x - from_x);
+        let move_energy = match (dy, dx) {
+            (0, 0) => return 0,
+            _ if problem.move_model.is_diagonal(dy, dx) => self.move_energy.diagonal,
+            _ => self.move_energy.orthogonal,
+        };
+        move_energy + problem.world.cost_at(to_y, to_x)
+    }
+
+    /// Returns whether the battery is recharged at a location
+    pub fn is_station<T: CellValue>(&self, world: &World<T>, y: i64, x: i64) -> bool {
+        let location = world.wrap(y, x);
+        self.stations
+            .iter()
+            .any(|&(station_y, station_x)| world.wrap(station_y, station_x) == location)
+    }
+
+    /// Returns the energy left after stepping from one location to another with the given energy, or None if the
+    /// step uses more energy than there is. Arriving at a station recharges the battery
+    pub fn spend<T: CellValue>(
+        &self,
+        problem: &Problem<T>,
+        energy: i64,
+        from: (i64, i64),
+        (to_y, to_x): (i64, i64),
+    ) -> Option<i64> {
+        let left = energy - self.step_energy(problem, from, (to_y, to_x));
+        match left {
+            _ if left < 0 => None,
+            _ if self.is_station(&problem.world, to_y, to_x) => Some(self.capacity),
+            _ => Some(left),
+        }
+    }
+}
+
+/// The energy needed to get from every location of the world to the closest station, following the move model of the
+/// problem around obstacles. Used by algorithms to plan their visits to stations
+pub struct EnergyNeeds<T: CellValue = i64> {
+    world: Arc<World<T>>,
+    /// i64::MAX for locations from which no station can be reached
+    needs: Array2<i64>,
+}
+
+impl<T: CellValue> EnergyNeeds<T> {
+    /// Computes the needs with Dijkstra's algorithm from all passable stations, taking the steps of the move model
+    /// backwards. Steps onto locations with a negative cost are counted as using no energy
+    pub fn new(problem: &Problem<T>, energy: &EnergyModel) -> EnergyNeeds<T> {
+        let world = problem.world.clone();
+        let mut needs = Array2::from_elem((world.get_height(), world.get_width()), i64::MAX);
+        let mut queue = BinaryHeap::new();
+
+        for &(y, x) in energy.stations.iter() {
+            if world.is_passable(y, x) {
+                let (y, x) = world.wrap(y, x);
+                needs[[y as usize, x as usize]] = 0;
+                queue.push(Reverse((0, y, x)));
+            }
+        }
+
+        let moves = problem.move_model.all_moves();
+        while let Some(Reverse((need, y, x))) = queue.pop() {
+            if needs[[y as usize, x as usize]] < need {
+                continue;
+            }
+            for &(dy, dx) in moves.iter() {
+                // The location from which this step leads to the current location
+                let (from_y, from_x) = (y - dy, x - dx);
+                if !problem.move_model.is_legal((from_y, from_x), (y, x))
+                    || !world.is_passable(from_y, from_x)
+                {
+                    continue;
+                }
+                let from_need = need + energy.step_energy(problem, (from_y, from_x), (y, x)).max(0);
+                let (from_y, from_x) = world.wrap(from_y, from_x);
+                if from_need < needs[[from_y as usize, from_x as usize]] {
+                    needs[[from_y as usize, from_x as usize]] = from_need;
+                    queue.push(Reverse((from_need, from_y, from_x)));
+                }
+            }
+        }
+
+        EnergyNeeds { world, needs }
+    }
+
+    /// Returns the energy needed to get from a location to the closest station, or None if no station can be reached
+    pub fn need(&self, y: i64, x: i64) -> Option<i64> {
+        if !self.world.in_world(y, x) {
+            return None;
+        }
+        let (y, x) = self.world.wrap(y, x);
+        let need = self.needs[[y as usize, x as usize]];
+        (need != i64::MAX).then_some(need)
+    }
+
+    /// Returns the first step (dy, dx) of the cheapest way from a location to the closest station, or None if the
+    /// location is a station or no station can be reached from it
+    pub fn step_towards(
+        &self,
+        problem: &Problem<T>,
+        energy: &EnergyModel,
+        y: i64,
+        x: i64,
+    ) -> Option<(i64, i64)> {
+        let need = self.need(y, x).filter(|&need| 0 < need)?;
+        problem
+            .move_model
+            .moves(y)
+            .iter()
+            .copied()
+            .find(|&(dy, dx)| {
+                (dy, dx) != (0, 0)
+                    && problem.world.is_passable(y + dy, x + dx)
+                    && self.need(y + dy, x + dx).is_some_and(|next_need| {
+                        next_need + energy.step_energy(problem, (y, x), (y + dy, x + dx)).max(0)
+                            == need
+                    })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_structures::{move_model::MoveModel, problem::Agent};
+
+    fn problem(stations: Vec<(i64, i64)>) -> Problem {
+        let mut world = World::random(20, 20, 0.0, 1);
+        world.set_cost(4, 6, 5);
+        let energy = EnergyModel {
+            capacity: 10,
+            move_energy: MoveCost {
+                orthogonal: 2,
+                diagonal: 3,
+            },
+            stations,
+        };
+        let agents = vec![Agent::new(4, 4, Some(10))];
+        Problem::with_agents(Arc::new(world), 0, agents, 1).with_energy(Some(energy))
+    }
+
+    #[test]
+    fn a_step_uses_its_move_energy_and_the_cost_of_the_location() {
+        let problem = problem(vec![]);
+        let energy = problem.energy.as_ref().unwrap();
+        assert_eq!(energy.step_energy(&problem, (4, 4), (4, 4)), 0);
+        assert_eq!(energy.step_energy(&problem, (4, 4), (4, 5)), 2);
+        assert_eq!(energy.step_energy(&problem, (4, 4), (5, 5)), 3);
+        assert_eq!(energy.step_energy(&problem, (4, 5), (4, 6)), 2 + 5);
+    }
+
+    #[test]
+    fn the_battery_is_spent_and_recharged_at_stations() {
+        let problem = problem(vec![(5, 5)]);
+        let energy = problem.energy.as_ref().unwrap();
+        assert_eq!(energy.spend(&problem, 10, (4, 4), (4, 5)), Some(8));
+        assert_eq!(energy.spend(&problem, 2, (4, 4), (4, 5)), Some(0));
+        assert_eq!(energy.spend(&problem, 1, (4, 4), (4, 5)), None);
+        // Arriving at a station recharges the battery, but only if the step can be taken at all
+        assert_eq!(energy.spend(&problem, 3, (4, 4), (5, 5)), Some(10));
+        assert_eq!(energy.spend(&problem, 2, (4, 4), (5, 5)), None);
+        assert!(energy.is_station(&problem.world, 5, 5));
+        assert!(!energy.is_station(&problem.world, 5, 6));
+    }
+
+    #[test]
+    fn energy_needs_lead_to_the_closest_station() {
+        let four_connected =
+            problem(vec![(4, 4), (15, 15)]).with_move_model(MoveModel::FourConnected);
+        let energy = four_connected.energy.as_ref().unwrap();
+        let needs = EnergyNeeds::new(&four_connected, energy);
+        assert_eq!(needs.need(4, 4), Some(0));
+        assert_eq!(needs.need(4, 5), Some(2));
+        assert_eq!(needs.need(7, 4), Some(6));
+        // Stepping onto (4, 6) costs 5 on top of the move, so the way around it is cheaper
+        assert_eq!(needs.need(4, 7), Some(10));
+        assert_eq!(needs.need(14, 15), Some(2));
+        assert_eq!(needs.need(-1, 0), None);
+        assert_eq!(needs.step_towards(&four_connected, energy, 4, 4), None);
+        assert_eq!(
+            needs.step_towards(&four_connected, energy, 7, 4),
+            Some((-1, 0))
+        );
+
+        // Without stations, no location is within reach of one
+        let without_stations = problem(vec![]);
+        let energy = without_stations.energy.as_ref().unwrap();
+        let needs = EnergyNeeds::new(&without_stations, energy);
+        assert_eq!(needs.need(4, 4), None);
+    }
+}
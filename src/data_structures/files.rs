@@ -0,0 +1,33 @@
+use std::{fs, path::Path};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Reads a value from a TOML or JSON file. The format is derived from the file extension
+pub fn load<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let text =
+        fs::read_to_string(path).map_err(|e| eyre!("Could not open {}: {}", path.display(), e))?;
+
+    match extension(path).as_deref() {
+        Some("toml") => Ok(toml::from_str(&text)?),
+        Some("json") => Ok(serde_json::from_str(&text)?),
+        _ => Err(eyre!("Unknown file format: {}", path.display())),
+    }
+}
+
+/// Writes a value to a TOML or JSON file. The format is derived from the file extension
+pub fn save<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let text = match extension(path).as_deref() {
+        Some("toml") => toml::to_string_pretty(value)?,
+        Some("json") => serde_json::to_string_pretty(value)?,
+        _ => return Err(eyre!("Unknown file format: {}", path.display())),
+    };
+    fs::write(path, text).map_err(|e| eyre!("Could not write {}: {}", path.display(), e))
+}
+
+/// Returns the lowercase extension of a path, if any
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_lowercase())
+}
@@ -0,0 +1,194 @@
+use std::{collections::VecDeque, sync::Arc};
+
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    energy::{EnergyModel, EnergyNeeds},
+    problem::{Agent, Problem},
+};
+use crate::world::{cell::CellValue, Landscape, Topology, World};
+
+/// Where the path of every agent has to end, such as back at base
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Goal {
+    /// Back at the start location of the agent, closing the tour
+    Start,
+    /// Inside the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1
+    Region { y0: i64, y1: i64, x0: i64, x1: i64 },
+    /// Within a distance, in locations, of any of the landing sites (y, x)
+    LandingSites {
+        sites: Vec<(i64, i64)>,
+        distance: f64,
+    },
+}
+
+impl Goal {
+    /// Returns whether the path of the agent may end at a location
+    pub fn contains<T: CellValue>(&self, world: &World<T>, agent: &Agent, y: i64, x: i64) -> bool {
+        match *self {
+            Goal::Start => world.wrap(y, x) == world.wrap(agent.start_y, agent.start_x),
+            Goal::Region { y0, y1, x0, x1 } => world.block_contains((y0, y1, x0, x1), y, x),
+            Goal::LandingSites {
+                ref sites,
+                distance,
+            } => sites.iter().any(|&(site_y, site_x)| {
+                // On a toroidal world, the shortest way to a landing site may cross an edge
+                let (dy, dx) = match world.get_topology() {
+                    Topology::Bounded => (y - site_y, x - site_x),
+                    Topology::Toroidal => {
+                        let shortest = |d: i64, length: usize| {
+                            let d = d.rem_euclid(length as i64);
+                            d.min(length as i64 - d)
+                        };
+                        (
+                            shortest(y - site_y, world.get_height()),
+                            shortest(x - site_x, world.get_width()),
+                        )
+                    }
+                };
+                ((dy * dy + dx * dx) as f64).sqrt() <= distance
+            }),
+        }
+    }
+}
+
+/// The number of steps from every location of the world to the goal of an agent, following the move model of the
+/// problem around obstacles. Used by algorithms to keep enough steps to reach the goal
+pub struct GoalDistances<T: CellValue = i64> {
+    world: Arc<World<T>>,
+    /// u64::MAX for locations from which the goal can not be reached
+    distances: Array2<u64>,
+}
+
+impl<T: CellValue> GoalDistances<T> {
+    /// Computes the distances with a breadth first search from all passable locations of the goal, taking the steps of
+    /// the move model backwards
+    pub fn new(problem: &Problem<T>, goal: &Goal, agent: &Agent) -> GoalDistances<T> {
+        let world = problem.world.clone();
+        let (height, width) = (world.get_height(), world.get_width());
+        let mut distances = Array2::from_elem((height, width), u64::MAX);
+        let mut queue = VecDeque::new();
+
+        for y in 0..height as i64 {
+            for x in 0..width as i64 {
+                if world.is_passable(y, x) && goal.contains(&world, agent, y, x) {
+                    distances[[y as usize, x as usize]] = 0;
+                    queue.push_back((y, x));
+                }
+            }
+        }
+
+        let moves = problem.move_model.all_moves();
+        while let Some((y, x)) = queue.pop_front() {
+            let distance = distances[[y as usize, x as usize]];
+            for &(dy, dx) in moves.iter() {
+                // The location from which this step leads to the current location
+                let (from_y, from_x) = (y - dy, x - dx);
+                if !problem.move_model.is_legal((from_y, from_x), (y, x))
+                    || !world.is_passable(from_y, from_x)
+                {
+                    continue;
+                }
+                let (from_y, from_x) = world.wrap(from_y, from_x);
+                let from_distance = &mut distances[[from_y as usize, from_x as usize]];
+                if *from_distance == u64::MAX {
+                    *from_distance = distance + 1;
+                    queue.push_back((from_y, from_x));
+                }
+            }
+        }
+
+        GoalDistances { world, distances }
+    }
+
+    /// Returns the number of steps from a location to the goal, or None if the goal can not be reached from it
+    pub fn distance(&self, y: i64, x: i64) -> Option<u64> {
+        if !self.world.in_world(y, x) {
+            return None;
+        }
+        let (y, x) = self.world.wrap(y, x);
+        let distance = self.distances[[y as usize, x as usize]];
+        (distance != u64::MAX).then_some(distance)
+    }
+
+    /// Returns the first step (dy, dx) of the shortest way from a location to the goal, or None if the location lies
+    /// in the goal or the goal can not be reached from it
+    pub fn step_towards(&self, problem: &Problem<T>, y: i64, x: i64) -> Option<(i64, i64)> {
+        let distance = self.distance(y, x).filter(|&distance| 0 < distance)?;
+        problem
+            .move_model
+            .moves(y)
+            .iter()
+            .copied()
+            .find(|&(dy, dx)| self.distance(y + dy, x + dx) == Some(distance - 1))
+    }
+
+    /// Returns whether the goal can be reached from a location within steps_left steps, by following the shortest way
+    /// to it. If the agents have a battery, the given energy has to suffice, recharging at stations along the way. If it
+    /// does not, the agent may first take the cheapest way to the closest station, and recharge there
+    pub fn reachable(
+        &self,
+        problem: &Problem<T>,
+        energy_needs: Option<&EnergyNeeds<T>>,
+        (y, x): (i64, i64),
+        steps_left: u64,
+        energy: i64,
+    ) -> bool {
+        let Some(model) = &problem.energy else {
+            return self
+                .distance(y, x)
+                .is_some_and(|distance| distance <= steps_left);
+        };
+        if self.reachable_directly(problem, model, (y, x), steps_left, energy) {
+            return true;
+        }
+        let Some(energy_needs) = energy_needs else {
+            return false;
+        };
+
+        // Follow the cheapest way to the closest station
+        let (mut y, mut x, mut energy, mut steps_left) = (y, x, energy, steps_left);
+        while !model.is_station(&self.world, y, x) {
+            let Some((dy, dx)) = energy_needs.step_towards(problem, model, y, x) else {
+                return false;
+            };
+            match (
+                model.spend(problem, energy, (y, x), (y + dy, x + dx)),
+                steps_left.checked_sub(1),
+            ) {
+                (Some(left), Some(steps)) => (energy, steps_left) = (left, steps),
+                _ => return false,
+            }
+            (y, x) = (y + dy, x + dx);
+        }
+        self.reachable_directly(problem, model, (y, x), steps_left, energy)
+    }
+
+    /// Returns whether the goal can be reached from a location within steps_left steps and with the given energy, by
+    /// following the shortest way to it
+    fn reachable_directly(
+        &self,
+        problem: &Problem<T>,
+        model: &EnergyModel,
+        (mut y, mut x): (i64, i64),
+        steps_left: u64,
+        mut energy: i64,
+    ) -> bool {
+        if self
+            .distance(y, x)
+            .is_none_or(|distance| steps_left < distance)
+        {
+            return false;
+        }
+        while let Some((dy, dx)) = self.step_towards(problem, y, x) {
+            match model.spend(problem, energy, (y, x), (y + dy, x + dx)) {
+                Some(left) => energy = left,
+                None => return false,
+            }
+            (y, x) = (y + dy, x + dx);
+        }
+        true
+    }
+}
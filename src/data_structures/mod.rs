@@ -1,3 +1,8 @@
 pub mod colors;
+pub mod energy;
+pub mod files;
+pub mod goal;
+pub mod move_model;
 pub mod problem;
+pub mod problem_spec;
 pub mod solution;
@@ -0,0 +1,164 @@
+use serde::{Deserialize, Serialize};
+
+/// Steps (dy, dx) to the 4 locations that share a side with the current location
+const FOUR_CONNECTED: [(i64, i64); 4] = [(-1, 0), (0, -1), (0, 1), (1, 0)];
+
+/// Steps (dy, dx) to the 8 locations around the current location
+const EIGHT_CONNECTED: [(i64, i64); 8] = [
+    (-1, -1),
+    (-1, 0),
+    (-1, 1),
+    (0, -1),
+    (0, 1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+];
+
+/// Steps (dy, dx) to the 6 neighbours of a hexagon on an even row. Odd rows are shifted half a hexagon to the right
+const HEXAGONAL_EVEN: [(i64, i64); 6] = [(-1, -1), (-1, 0), (0, -1), (0, 1), (1, -1), (1, 0)];
+const HEXAGONAL_ODD: [(i64, i64); 6] = [(-1, 0), (-1, 1), (0, -1), (0, 1), (1, 0), (1, 1)];
+
+/// Defines the steps that can be taken from a location
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MoveModel {
+    /// Up, down, left and right
+    FourConnected,
+    /// Up, down, left, right and the diagonals
+    #[default]
+    EightConnected,
+    /// The world is laid out as hexagons in rows, where odd rows are shifted half a hexagon to the right. A toroidal
+    /// world needs an even number of rows, so that the rows keep alternating across the edge
+    Hexagonal,
+    /// Any list of steps (dy, dx), such as larger jumps
+    Custom(Vec<(i64, i64)>),
+}
+
+impl MoveModel {
+    /// Returns the steps (dy, dx) that can be taken from a location on row y
+    pub fn moves(&self, y: i64) -> &[(i64, i64)] {
+        match self {
+            MoveModel::FourConnected => &FOUR_CONNECTED,
+            MoveModel::EightConnected => &EIGHT_CONNECTED,
+            MoveModel::Hexagonal if y.rem_euclid(2) == 0 => &HEXAGONAL_EVEN,
+            MoveModel::Hexagonal => &HEXAGONAL_ODD,
+            MoveModel::Custom(moves) => moves,
+        }
+    }
+
+    /// Returns the steps (dy, dx) that can be taken from a location on any row, sorted and without staying in place.
+    /// Hexagonal steps depend on whether the row is even or odd, so the steps of both are included
+    pub fn all_moves(&self) -> Vec<(i64, i64)> {
+        let mut moves: Vec<(i64, i64)> = [0, 1]
+            .iter()
+            .flat_map(|&y| self.moves(y))
+            .copied()
+            .filter(|&step| step != (0, 0))
+            .collect();
+        moves.sort();
+        moves.dedup();
+        moves
+    }
+
+    /// Returns whether a step from one location to another is allowed
+    pub fn is_legal(&self, (from_y, from_x): (i64, i64), (to_y, to_x): (i64, i64)) -> bool {
+        self.moves(from_y).contains(&(to_y - from_y, to_x - from_x))
+    }
+
+    /// Returns whether a step counts as diagonal for its cost. All neighbours of a hexagon are equally far away
+    pub fn is_diagonal(&self, dy: i64, dx: i64) -> bool {
+        match self {
+            MoveModel::Hexagonal => false,
+            _ => dy != 0 && dx != 0,
+        }
+    }
+
+    /// Returns the step from row y that points most closely in the direction (dy, dx), if any step points towards it
+    pub fn closest_move(&self, y: i64, dy: i64, dx: i64) -> Option<(i64, i64)> {
+        let cosine = |&(my, mx): &(i64, i64)| {
+            (my * dy + mx * dx) as f64 / (((my * my + mx * mx) * (dy * dy + dx * dx)) as f64).sqrt()
+        };
+        self.moves(y)
+            .iter()
+            .filter(|&&step| step != (0, 0) && 0. < cosine(&step))
+            .fold(None, |best: Option<(i64, i64)>, &step| match best {
+                Some(best) if cosine(&step) <= cosine(&best) => Some(best),
+                _ => Some(step),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn four_connected_moves_share_a_side() {
+        let model = MoveModel::FourConnected;
+        for step in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            assert!(model.is_legal((5, 5), (5 + step.0, 5 + step.1)));
+        }
+        for step in [(-1, -1), (1, 1), (0, 0), (0, 2)] {
+            assert!(!model.is_legal((5, 5), (5 + step.0, 5 + step.1)));
+        }
+    }
+
+    #[test]
+    fn hexagonal_moves_depend_on_the_row() {
+        let model = MoveModel::Hexagonal;
+        // Even rows reach the row above and below to the left, odd rows to the right
+        for y in [-2, 0, 4] {
+            assert!(model.is_legal((y, 3), (y - 1, 2)));
+            assert!(model.is_legal((y, 3), (y + 1, 2)));
+            assert!(!model.is_legal((y, 3), (y - 1, 4)));
+            assert!(!model.is_legal((y, 3), (y + 1, 4)));
+        }
+        for y in [-1, 1, 5] {
+            assert!(model.is_legal((y, 3), (y - 1, 4)));
+            assert!(model.is_legal((y, 3), (y + 1, 4)));
+            assert!(!model.is_legal((y, 3), (y - 1, 2)));
+            assert!(!model.is_legal((y, 3), (y + 1, 2)));
+        }
+        // Every step can be taken back
+        for y in -2..2 {
+            for &(dy, dx) in model.moves(y) {
+                assert!(
+                    model.is_legal((y + dy, dx), (y, 0)),
+                    "({}, {}) from row {}",
+                    dy,
+                    dx,
+                    y
+                );
+            }
+        }
+        assert!(!model.is_diagonal(1, 1));
+        assert_eq!(model.all_moves().len(), 8);
+    }
+
+    #[test]
+    fn custom_moves_are_the_only_legal_steps() {
+        let model = MoveModel::Custom(vec![(0, 0), (2, 1), (-1, -2)]);
+        assert!(model.is_legal((0, 0), (2, 1)));
+        assert!(model.is_legal((3, 3), (2, 1)));
+        assert!(model.is_legal((3, 3), (3, 3)));
+        assert!(!model.is_legal((0, 0), (1, 0)));
+        assert_eq!(model.all_moves(), vec![(-1, -2), (2, 1)]);
+        assert!(model.is_diagonal(2, 1));
+    }
+
+    #[test]
+    fn closest_move_points_towards_the_direction() {
+        assert_eq!(MoveModel::FourConnected.closest_move(0, 1, 1), Some((0, 1)));
+        assert_eq!(
+            MoveModel::EightConnected.closest_move(0, 1, 1),
+            Some((1, 1))
+        );
+        assert_eq!(
+            MoveModel::EightConnected.closest_move(0, -3, 1),
+            Some((-1, 0))
+        );
+        assert_eq!(MoveModel::Hexagonal.closest_move(1, 1, 1), Some((1, 1)));
+        assert_eq!(MoveModel::Custom(vec![(0, 1)]).closest_move(0, 0, -1), None);
+    }
+}
@@ -1,12 +1,442 @@
-use derive_more::Constructor;
+use derive_more::{Constructor, Display};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, OnceLock},
+};
 
-use crate::world::World;
+use super::{
+    energy::{EnergyModel, EnergyNeeds},
+    goal::{Goal, GoalDistances},
+    move_model::MoveModel,
+};
+use crate::world::{
+    attempt::AttemptWorld,
+    cell::{CellSum, CellValue},
+    harvest::HarvestModel,
+    overlay::VisitOverlay,
+    regenerating::{RegeneratingWorld, Regeneration},
+    Landscape, World,
+};
 
-#[derive(Clone, Constructor)]
-pub struct Problem {
-    pub world: World,
+/// The problem to be solved. The seed is the seed from which the world and the start locations were generated,
+/// so that any run can be replayed. The world is shared and never modified. Algorithms visit it through an overlay.
+/// One or more agents walk a path each, collecting from the same world, so that a location harvested by one agent
+/// is gone for the others. The objective is the joint net score of the paths: the value collected along them, minus
+/// the cost of travelling them. The values of the world, and with them the scores, can be of any cell type
+#[derive(Clone)]
+pub struct Problem<T: CellValue = i64> {
+    pub world: Arc<World<T>>,
     pub milliseconds: u64,
-    pub n_steps: u64,
+    pub agents: Vec<Agent>,
+    pub seed: u64,
+    /// How much of the value of a location is collected by a visit
+    pub harvest: HarvestModel,
+    pub move_cost: MoveCost,
+    /// The steps that can be taken from a location
+    pub move_model: MoveModel,
+    /// Where the path of every agent has to end, if anywhere
+    pub goal: Option<Goal>,
+    /// The battery of the agents, if they have one
+    pub energy: Option<EnergyModel>,
+    /// How visited locations regrow over time, if they do. Regeneration replaces the harvest model, as every visit
+    /// collects the value that has grown back since the last visit
+    pub regeneration: Option<Regeneration>,
+    /// Shared by all copies of the problem, so that every attempt of every algorithm uses the same guidance
+    guidance: Arc<Guidance<T>>,
+}
+
+/// Distances to the goal by start location if the goal is the start of the agents, otherwise under None
+type GoalDistancesByStart<T> = HashMap<Option<(i64, i64)>, Arc<GoalDistances<T>>>;
+
+/// What algorithms need to plan their paths, computed on first use: the distances to the goal, and the energy needed
+/// to get to a station
+struct Guidance<T: CellValue> {
+    goal_distances: Mutex<GoalDistancesByStart<T>>,
+    energy_needs: OnceLock<Arc<EnergyNeeds<T>>>,
+}
+
+impl<T: CellValue> Default for Guidance<T> {
+    fn default() -> Self {
+        Guidance {
+            goal_distances: Mutex::default(),
+            energy_needs: OnceLock::new(),
+        }
+    }
+}
+
+/// An agent, walking a path of at most n_steps locations from its start location
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Constructor)]
+pub struct Agent {
     pub start_y: i64,
     pub start_x: i64,
+    /// None if the path is only limited by the battery of the agent
+    pub n_steps: Option<u64>,
+}
+
+impl Agent {
+    /// Returns the number of steps the agent has left once its path has the given number of locations. Without a limit
+    /// on its steps, an agent always has steps left
+    pub fn steps_left(&self, length: u64) -> u64 {
+        self.n_steps
+            .map_or(u64::MAX, |n_steps| n_steps.saturating_sub(length))
+    }
+}
+
+/// The cost of a single step, depending on its direction. Added to the cost of the location that is moved onto
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MoveCost {
+    pub orthogonal: i64,
+    pub diagonal: i64,
+}
+
+/// The reasons a path can be invalid for a problem
+#[derive(Clone, Debug, Display, PartialEq, Eq)]
+pub enum PathError {
+    #[display("expected a path for each of the {expected} agents, but got {actual} paths")]
+    AgentCount { expected: usize, actual: usize },
+    #[display("the path does not start at the start location ({y}, {x})")]
+    WrongStart { y: i64, x: i64 },
+    #[display("the path has {length} locations, while at most {n_steps} are allowed")]
+    TooLong { length: usize, n_steps: u64 },
+    #[display("step {step} at ({y}, {x}) is outside of the world")]
+    OutOfWorld { step: usize, y: i64, x: i64 },
+    #[display("step {step} at ({y}, {x}) is blocked by an obstacle")]
+    Blocked { step: usize, y: i64, x: i64 },
+    #[display(
+        "step {step} from ({from_y}, {from_x}) to ({y}, {x}) is not allowed by the move model"
+    )]
+    IllegalMove {
+        step: usize,
+        from_y: i64,
+        from_x: i64,
+        y: i64,
+        x: i64,
+    },
+    #[display("step {step} to ({y}, {x}) uses more energy than is left")]
+    OutOfEnergy { step: usize, y: i64, x: i64 },
+    #[display("the path ends at ({y}, {x}), outside of the goal")]
+    MissesGoal { y: i64, x: i64 },
+    #[display("agent {agent}: {error}")]
+    Agent { agent: usize, error: Box<PathError> },
+}
+
+impl<T: CellValue> Problem<T> {
+    /// Creates a problem with the given agents, in which visits collect the entire value of a location, and moving to
+    /// any of the 8 locations around the current location is free. The pyramid that algorithms search is built for the
+    /// world, which is copied first if it is shared
+    pub fn with_agents(
+        mut world: Arc<World<T>>,
+        milliseconds: u64,
+        agents: Vec<Agent>,
+        seed: u64,
+    ) -> Problem<T> {
+        let factor = AttemptWorld::<T>::PYRAMID_FACTOR;
+        if world.get_pyramid(factor).is_none() {
+            Arc::make_mut(&mut world).build_pyramid(factor);
+        }
+        Problem {
+            world,
+            milliseconds,
+            agents,
+            seed,
+            harvest: HarvestModel::default(),
+            move_cost: MoveCost::default(),
+            move_model: MoveModel::default(),
+            goal: None,
+            energy: None,
+            regeneration: None,
+            guidance: Arc::default(),
+        }
+    }
+
+    pub fn with_harvest(mut self, harvest: HarvestModel) -> Problem<T> {
+        self.harvest = harvest;
+        self
+    }
+
+    pub fn with_move_cost(mut self, move_cost: MoveCost) -> Problem<T> {
+        self.move_cost = move_cost;
+        self
+    }
+
+    pub fn with_move_model(mut self, move_model: MoveModel) -> Problem<T> {
+        self.move_model = move_model;
+        self.guidance = Arc::default();
+        self
+    }
+
+    pub fn with_goal(mut self, goal: Option<Goal>) -> Problem<T> {
+        self.goal = goal;
+        self.guidance = Arc::default();
+        self
+    }
+
+    pub fn with_energy(mut self, energy: Option<EnergyModel>) -> Problem<T> {
+        self.energy = energy;
+        self.guidance = Arc::default();
+        self
+    }
+
+    pub fn with_regeneration(mut self, regeneration: Option<Regeneration>) -> Problem<T> {
+        self.regeneration = regeneration;
+        self
+    }
+
+    /// Returns whether the path of the agent may end at a location. Any location will do if the problem has no goal
+    pub fn in_goal(&self, agent: &Agent, y: i64, x: i64) -> bool {
+        self.goal
+            .as_ref()
+            .is_none_or(|goal| goal.contains(&self.world, agent, y, x))
+    }
+
+    /// Returns the total value within reach of the given agents, counting locations within reach of several agents
+    /// once. A path of n_steps locations takes n_steps - 1 steps
+    pub fn reachable_value(&self, agents: &[Agent]) -> T::Sum {
+        let starts: Vec<(i64, i64, u64)> = agents
+            .iter()
+            .map(|agent| (agent.start_y, agent.start_x, agent.steps_left(1)))
+            .collect();
+        self.world.reachable_value(&self.move_model, &starts)
+    }
+
+    /// Returns the number of steps from every location to the goal of the agent, or None if the problem has no goal.
+    /// The distances are computed once, and shared by all agents with the same goal
+    pub fn goal_distances(&self, agent: &Agent) -> Option<Arc<GoalDistances<T>>> {
+        let goal = self.goal.as_ref()?;
+        let key = matches!(goal, Goal::Start).then_some((agent.start_y, agent.start_x));
+        let mut goal_distances = self.guidance.goal_distances.lock().unwrap();
+        Some(
+            goal_distances
+                .entry(key)
+                .or_insert_with(|| Arc::new(GoalDistances::new(self, goal, agent)))
+                .clone(),
+        )
+    }
+
+    /// Returns the energy needed to get from every location to a station, or None if the agents have no battery. The
+    /// needs are computed once
+    pub fn energy_needs(&self) -> Option<Arc<EnergyNeeds<T>>> {
+        let energy = self.energy.as_ref()?;
+        Some(
+            self.guidance
+                .energy_needs
+                .get_or_init(|| Arc::new(EnergyNeeds::new(self, energy)))
+                .clone(),
+        )
+    }
+
+    /// Creates a fresh view of the world for a single attempt, in which visits are harvested according to the problem,
+    /// or regenerate if the problem has a regeneration model
+    pub fn attempt(&self) -> AttemptWorld<T> {
+        match self.regeneration {
+            Some(regeneration) => {
+                AttemptWorld::Regenerating(RegeneratingWorld::new(self.world.clone(), regeneration))
+            }
+            None => {
+                AttemptWorld::Overlay(VisitOverlay::with_harvest(self.world.clone(), self.harvest))
+            }
+        }
+    }
+
+    /// Returns the cost of stepping from one location to another. Staying in place is free
+    pub fn step_cost(&self, (from_y, from_x): (i64, i64), (to_y, to_x): (i64, i64)) -> i64 {
+        let (dy, dx) = (to_y - from_y, to_x - from_x);
+        let move_cost = match (dy, dx) {
+            (0, 0) => return 0,
+            _ if self.move_model.is_diagonal(dy, dx) => self.move_cost.diagonal,
+            _ => self.move_cost.orthogonal,
+        };
+        move_cost + self.world.cost_at(to_y, to_x)
+    }
+
+    /// Returns the cost of travelling along a path
+    pub fn path_cost(&self, path: &[(i64, i64)]) -> i64 {
+        path.windows(2)
+            .map(|step| self.step_cost(step[0], step[1]))
+            .sum()
+    }
+
+    /// Returns the joint net score of the paths of the agents, by visiting every location of the paths one after
+    /// another in a single view of the world, and subtracting the cost of travelling them. The i-th location of a path
+    /// is visited at timestep i, as the algorithms do. Without regeneration, how much is collected from a location
+    /// only depends on how often it is visited, so the order in which the paths are visited does not change the joint
+    /// score. The scores are summed in the same order as the algorithms sum them, so that floating point scores match
+    pub fn score_paths(&self, paths: &[Vec<(i64, i64)>]) -> T::Sum {
+        let mut world = self.attempt();
+        let mut score = T::Sum::default();
+        for path in paths {
+            let mut collected = T::Sum::default();
+            for (t, &(y, x)) in (0..).zip(path) {
+                if let Some(value) = world.visit_by(y, x, t, "") {
+                    collected += value.into();
+                }
+            }
+            score += collected - T::Sum::from_i64(self.path_cost(path));
+        }
+        score
+    }
+
+    /// Checks that there is a path for every agent, that it starts at the start location of the agent, that it does not
+    /// exceed the number of steps of the agent if it has a limit, and that it ends in the goal of the problem, if any. Every path is
+    /// validated with `validate_path`
+    pub fn validate_paths(&self, paths: &[Vec<(i64, i64)>]) -> Result<(), PathError> {
+        if paths.len() != self.agents.len() {
+            return Err(PathError::AgentCount {
+                expected: self.agents.len(),
+                actual: paths.len(),
+            });
+        }
+        for (index, (path, agent)) in paths.iter().zip(self.agents.iter()).enumerate() {
+            let error = if path.first() != Some(&(agent.start_y, agent.start_x)) {
+                PathError::WrongStart {
+                    y: agent.start_y,
+                    x: agent.start_x,
+                }
+            } else if let Some(n_steps) =
+                agent.n_steps.filter(|&n_steps| n_steps < path.len() as u64)
+            {
+                PathError::TooLong {
+                    length: path.len(),
+                    n_steps,
+                }
+            } else if let Err(error) = self.validate_path(path) {
+                error
+            } else if let Some(&(y, x)) = path.last().filter(|&&(y, x)| !self.in_goal(agent, y, x))
+            {
+                PathError::MissesGoal { y, x }
+            } else {
+                continue;
+            };
+            return Err(PathError::Agent {
+                agent: index,
+                error: Box::new(error),
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks that every location of the path lies within the world and is not blocked by an obstacle, that every step
+    /// is allowed by the move model, and that the energy of the agent never drops below zero
+    pub fn validate_path(&self, path: &[(i64, i64)]) -> Result<(), PathError> {
+        for (step, &(y, x)) in path.iter().enumerate() {
+            if let Some(&(from_y, from_x)) = step.checked_sub(1).map(|previous| &path[previous]) {
+                if !self.move_model.is_legal((from_y, from_x), (y, x)) {
+                    return Err(PathError::IllegalMove {
+                        step,
+                        from_y,
+                        from_x,
+                        y,
+                        x,
+                    });
+                }
+            }
+            if !self.world.in_world(y, x) {
+                return Err(PathError::OutOfWorld { step, y, x });
+            }
+            if self.world.is_blocked(y, x) {
+                return Err(PathError::Blocked { step, y, x });
+            }
+        }
+
+        // Every step uses energy, and the battery is recharged at stations
+        if let Some(model) = &self.energy {
+            let mut energy = model.capacity;
+            for (step, locations) in path.windows(2).enumerate() {
+                let (y, x) = locations[1];
+                energy = model.spend(self, energy, locations[0], (y, x)).ok_or(
+                    PathError::OutOfEnergy {
+                        step: step + 1,
+                        y,
+                        x,
+                    },
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(move_model: MoveModel) -> Problem {
+        let world = Arc::new(World::random(20, 20, 0.0, 1));
+        Problem::with_agents(world, 0, vec![Agent::new(4, 4, Some(10))], 1)
+            .with_move_model(move_model)
+    }
+
+    #[test]
+    fn steps_outside_of_the_move_model_are_rejected() {
+        let problem = problem(MoveModel::FourConnected);
+        assert_eq!(problem.validate_path(&[(4, 4), (4, 5), (5, 5)]), Ok(()));
+        assert_eq!(
+            problem.validate_path(&[(4, 4), (4, 5), (5, 6)]),
+            Err(PathError::IllegalMove {
+                step: 2,
+                from_y: 4,
+                from_x: 5,
+                y: 5,
+                x: 6
+            })
+        );
+    }
+
+    #[test]
+    fn hexagonal_steps_are_checked_against_their_row() {
+        let problem = problem(MoveModel::Hexagonal);
+        // From the even row 4 the row below is reached to the left, from the odd row 5 to the right
+        assert_eq!(problem.validate_path(&[(4, 4), (5, 3), (6, 4)]), Ok(()));
+        let error = problem.validate_paths(&[vec![(4, 4), (5, 5)]]).unwrap_err();
+        assert_eq!(
+            error,
+            PathError::Agent {
+                agent: 0,
+                error: Box::new(PathError::IllegalMove {
+                    step: 1,
+                    from_y: 4,
+                    from_x: 4,
+                    y: 5,
+                    x: 5
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn a_path_that_runs_out_of_energy_is_rejected() {
+        let energy = EnergyModel {
+            capacity: 5,
+            move_energy: MoveCost {
+                orthogonal: 2,
+                diagonal: 3,
+            },
+            stations: vec![(4, 6)],
+        };
+        let problem = problem(MoveModel::EightConnected).with_energy(Some(energy));
+        // A second diagonal step uses more energy than is left
+        assert_eq!(
+            problem.validate_path(&[(4, 4), (5, 5), (6, 6), (7, 7)]),
+            Err(PathError::OutOfEnergy {
+                step: 2,
+                y: 6,
+                x: 6
+            })
+        );
+        // Recharging at the station keeps the battery going for two more steps, but not three
+        assert_eq!(
+            problem.validate_path(&[(4, 4), (4, 5), (4, 6), (4, 7), (4, 8)]),
+            Ok(())
+        );
+        assert_eq!(
+            problem.validate_path(&[(4, 4), (4, 5), (4, 6), (4, 7), (4, 8), (4, 9)]),
+            Err(PathError::OutOfEnergy {
+                step: 5,
+                y: 4,
+                x: 9
+            })
+        );
+    }
 }
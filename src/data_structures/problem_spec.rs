@@ -0,0 +1,740 @@
+use std::{
+    any::Any,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use color_eyre::eyre::{bail, eyre, Result};
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    energy::EnergyModel,
+    files,
+    goal::Goal,
+    move_model::MoveModel,
+    problem::{Agent, MoveCost, Problem},
+};
+use crate::{
+    algorithms::AlgorithmKind,
+    world::{
+        cell::CellValue, generator::NoiseConfig, harvest::HarvestModel, regenerating::Regeneration,
+        tiled::TiledWorld, Landscape, Topology, World,
+    },
+};
+
+/// A problem as written down in a TOML or JSON file, so that problems can be shared and run by name. Everything
+/// that is left out takes the value of the default problem. Seeds that are left out are drawn at random
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProblemSpec {
+    pub world: WorldSpec,
+    pub milliseconds: u64,
+    /// Maximum number of locations of a path. If left out, paths have at most 1000 locations, unless the agents have a
+    /// battery, which then is the only limit on their paths
+    pub n_steps: Option<u64>,
+    /// Start location as (y, x). If left out, a random passable location is drawn from the seed
+    pub start: Option<(i64, i64)>,
+    /// The agents collecting from the world. If left out, a single agent walks n_steps from the start location
+    pub agents: Vec<AgentSpec>,
+    /// Where the path of every agent has to end. If left out, paths can end anywhere
+    pub goal: Option<Goal>,
+    /// The battery of the agents. If left out, agents are only limited by their number of steps
+    pub energy: Option<EnergyModel>,
+    /// How visited locations regrow over time. If left out, visited locations stay harvested. Only a single agent can
+    /// collect from a regenerating world, and every visit collects the value that has grown back
+    pub regeneration: Option<Regeneration>,
+    /// Seed from which the start locations, and the world if it has no seed of its own, are generated
+    pub seed: Option<u64>,
+    pub harvest: HarvestModel,
+    pub move_cost: MoveCost,
+    pub move_model: MoveModel,
+    pub algorithms: Vec<AlgorithmKind>,
+}
+
+/// An agent of a problem. Everything that is left out takes the value of the problem
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AgentSpec {
+    /// Start location as (y, x). If left out, a random passable location is drawn from the seed
+    pub start: Option<(i64, i64)>,
+    pub n_steps: Option<u64>,
+}
+
+/// The world of a problem, either generated or loaded from a file
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorldSpec {
+    #[serde(flatten)]
+    pub source: WorldSource,
+    #[serde(default)]
+    pub topology: Topology,
+    /// Fraction of locations that are blocked at random, drawn from the seed of the world. Only applies to generated
+    /// worlds, as a world file holds its own obstacles
+    /// fraction: 0.0 - 1.0
+    #[serde(default)]
+    pub obstacles: f64,
+    /// Cost of moving onto every location, drawn from the seed of the world. Only applies to generated worlds, as a
+    /// world file holds its own costs. If left out, moving onto a location is free
+    #[serde(default)]
+    pub costs: Option<CostSpec>,
+    /// The type of the values of the world, and with it of the scores of the problem
+    #[serde(default)]
+    pub cell_type: CellType,
+}
+
+/// The types of values a world of a problem can hold
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellType {
+    /// Whole values between 0 and 100, unless a world file says otherwise
+    #[default]
+    I64,
+    /// Fractional values between 0 and 1, such as probabilities
+    F64,
+}
+
+/// The cost layer of a world, generated from noise so that costs vary smoothly like terrain
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CostSpec {
+    /// Costs lie between 0 and max_cost
+    pub max_cost: i64,
+    #[serde(default)]
+    pub noise: NoiseConfig,
+}
+
+/// The generators a world can be created with, and their parameters. Seeds that are left out are taken from the
+/// problem
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "generator", rename_all = "snake_case")]
+pub enum WorldSource {
+    Random {
+        height: usize,
+        width: usize,
+        sparsity: f64,
+        seed: Option<u64>,
+    },
+    Perlin {
+        height: usize,
+        width: usize,
+        sparsity: f64,
+        seed: Option<u64>,
+    },
+    Noise {
+        height: usize,
+        width: usize,
+        sparsity: f64,
+        #[serde(default)]
+        noise: NoiseConfig,
+        seed: Option<u64>,
+    },
+    Hotspots {
+        height: usize,
+        width: usize,
+        n: usize,
+        amplitude: i64,
+        radius: f64,
+        seed: Option<u64>,
+    },
+    Gradient {
+        height: usize,
+        width: usize,
+        angle: f64,
+    },
+    Corridors {
+        height: usize,
+        width: usize,
+        n: usize,
+        corridor_width: f64,
+        value: i64,
+        seed: Option<u64>,
+    },
+    Maze {
+        height: usize,
+        width: usize,
+        seed: Option<u64>,
+    },
+    /// A window of height by width locations onto an unbounded `TiledWorld`, starting at origin (y, x). Only the
+    /// tiles the window covers are generated, after which the window is copied into a dense `World` that the problem
+    /// runs on. The scale is the number of locations spanned by a unit of noise
+    Tiled {
+        height: usize,
+        width: usize,
+        scale: f64,
+        sparsity: f64,
+        #[serde(default)]
+        noise: NoiseConfig,
+        #[serde(default)]
+        origin: (i64, i64),
+        seed: Option<u64>,
+    },
+    /// A world file in any of the formats `World::load` supports
+    File { path: PathBuf },
+}
+
+/// The problem that used to be hardcoded: a 100x100 perlin world, 15 seconds and 1000 steps, with all algorithms
+impl Default for ProblemSpec {
+    fn default() -> Self {
+        ProblemSpec {
+            world: WorldSpec {
+                source: WorldSource::Perlin {
+                    height: 100,
+                    width: 100,
+                    sparsity: 0.5,
+                    seed: None,
+                },
+                topology: Topology::default(),
+                obstacles: 0.0,
+                costs: None,
+                cell_type: CellType::default(),
+            },
+            milliseconds: 15000,
+            n_steps: None,
+            start: None,
+            agents: Vec::new(),
+            goal: None,
+            energy: None,
+            regeneration: None,
+            seed: None,
+            harvest: HarvestModel::default(),
+            move_cost: MoveCost::default(),
+            move_model: MoveModel::default(),
+            algorithms: AlgorithmKind::ALL.to_vec(),
+        }
+    }
+}
+
+impl ProblemSpec {
+    /// Directory in which problems are looked up by name
+    pub const DIRECTORY: &'static str = "problems";
+    /// Number of locations of a path, if neither the problem nor its agents have one and the agents have no battery
+    pub const DEFAULT_N_STEPS: u64 = 1000;
+
+    /// Loads a problem from a TOML or JSON file. The format is derived from the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<ProblemSpec> {
+        files::load(path.as_ref())
+    }
+
+    /// Finds a problem by name in the problems directory, as either a TOML or a JSON file. A path to a problem file
+    /// is accepted as well
+    pub fn find(name: &str) -> Result<ProblemSpec> {
+        if Path::new(name).is_file() {
+            return ProblemSpec::load(name);
+        }
+        ["toml", "json"]
+            .iter()
+            .map(|extension| Path::new(Self::DIRECTORY).join(format!("{}.{}", name, extension)))
+            .find(|path| path.is_file())
+            .ok_or_else(|| eyre!("No problem named {} in {}", name, Self::DIRECTORY))
+            .and_then(ProblemSpec::load)
+    }
+
+    /// Returns the names of all problems in the problems directory, sorted by name
+    pub fn names() -> Result<Vec<String>> {
+        let mut names: Vec<String> = std::fs::read_dir(Self::DIRECTORY)
+            .map_err(|e| eyre!("Could not read {}: {}", Self::DIRECTORY, e))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                matches!(
+                    path.extension().and_then(|extension| extension.to_str()),
+                    Some("toml" | "json")
+                )
+            })
+            .filter_map(|path| Some(path.file_stem()?.to_str()?.to_string()))
+            .collect();
+        names.sort();
+        names.dedup();
+        Ok(names)
+    }
+
+    /// Returns a copy of this problem with the seed and the agents of the given problem, which was built from it.
+    /// Building the copy results in the same problem, even if this problem draws its seed at random
+    pub fn resolved<T: CellValue>(&self, problem: &Problem<T>) -> ProblemSpec {
+        let mut resolved = ProblemSpec {
+            seed: Some(problem.seed),
+            ..self.clone()
+        };
+        match problem.agents.as_slice() {
+            [agent] if self.agents.is_empty() => {
+                resolved.start = Some((agent.start_y, agent.start_x))
+            }
+            agents => {
+                resolved.agents = agents
+                    .iter()
+                    .map(|agent| AgentSpec {
+                        start: Some((agent.start_y, agent.start_x)),
+                        n_steps: agent.n_steps,
+                    })
+                    .collect()
+            }
+        }
+        resolved
+    }
+
+    /// Creates the problem. If the problem has no seed, one is drawn at random. Start locations that are left out are
+    /// drawn one after the other from the seed, so that a single agent starts where it would without agents. Fails if
+    /// an agent does not start on a passable location of the world, has no steps to take, or can not reach the goal
+    /// within its number of steps and the energy of its battery, if hexagonal moves are used on a toroidal world
+    /// with an odd number of rows, or if a regenerating world is shared by several agents or not harvested in full.
+    /// The world holds values of type T, which is meant to be the cell type of the world spec
+    pub fn build<T: CellValue>(&self) -> Result<Problem<T>> {
+        let seed = self.seed.unwrap_or_else(rand::random);
+        let world = Arc::new(self.world.build(seed)?);
+        // Hexagonal rows alternate, so a toroidal world has to wrap from an odd row onto an even one
+        if self.move_model == MoveModel::Hexagonal
+            && world.get_topology() == Topology::Toroidal
+            && world.get_height() % 2 == 1
+        {
+            bail!(
+                "Hexagonal moves need an even height on a toroidal world, but the world has {} rows",
+                world.get_height()
+            );
+        }
+
+        // The time of a visit restarts for every agent, so agents can not share a world that regenerates over time
+        if self.regeneration.is_some() {
+            if 1 < self.agents.len() {
+                bail!(
+                    "A regenerating world has a single agent, but the problem has {} agents",
+                    self.agents.len()
+                );
+            }
+            if self.harvest != HarvestModel::Full {
+                bail!("A regenerating world can only be harvested in full");
+            }
+        }
+
+        let agents = match self.agents.is_empty() {
+            true => vec![AgentSpec::default()],
+            false => self.agents.clone(),
+        };
+        let mut rng = StdRng::seed_from_u64(seed);
+        let agents = agents
+            .iter()
+            .map(|agent| {
+                let (start_y, start_x) = match agent.start.or(self.start) {
+                    Some(start) => start,
+                    None => world
+                        .random_passable_location(&mut rng)
+                        .ok_or_else(|| eyre!("World has no passable location to start from"))?,
+                };
+                // Without a battery, the number of steps is the only limit on a path
+                let n_steps = agent
+                    .n_steps
+                    .or(self.n_steps)
+                    .or(self.energy.is_none().then_some(Self::DEFAULT_N_STEPS));
+                Ok(Agent::new(start_y, start_x, n_steps))
+            })
+            .collect::<Result<Vec<Agent>>>()?;
+        for (index, agent) in agents.iter().enumerate() {
+            if !world.in_world(agent.start_y, agent.start_x) {
+                bail!(
+                    "Agent {} starts at ({}, {}), outside of the world of {}x{}",
+                    index,
+                    agent.start_y,
+                    agent.start_x,
+                    world.get_height(),
+                    world.get_width()
+                );
+            }
+            if !world.is_passable(agent.start_y, agent.start_x) {
+                bail!(
+                    "Agent {} starts at ({}, {}), on an obstacle",
+                    index,
+                    agent.start_y,
+                    agent.start_x
+                );
+            }
+            if agent.n_steps == Some(0) {
+                bail!("Agent {} has no steps to take", index);
+            }
+            if let (None, Some(energy)) = (agent.n_steps, &self.energy) {
+                // Only a battery that drains with every step keeps a path without a limit on its steps finite
+                let min_cost = world
+                    .get_costs()
+                    .and_then(|costs| costs.iter().min().copied())
+                    .unwrap_or(0)
+                    .min(0);
+                let min_energy = energy
+                    .move_energy
+                    .orthogonal
+                    .min(energy.move_energy.diagonal)
+                    + min_cost;
+                if min_energy <= 0 {
+                    bail!(
+                        "Agent {} has no limit on its steps, but steps can use {} energy",
+                        index,
+                        min_energy
+                    );
+                }
+                if self.goal.is_some() {
+                    bail!(
+                        "Agent {} needs a limit on its steps to reach the goal",
+                        index
+                    );
+                }
+            }
+        }
+
+        let problem = Problem::with_agents(world, self.milliseconds, agents, seed)
+            .with_harvest(self.harvest)
+            .with_move_cost(self.move_cost)
+            .with_move_model(self.move_model.clone())
+            .with_goal(self.goal.clone())
+            .with_energy(self.energy.clone())
+            .with_regeneration(self.regeneration);
+
+        let energy_needs = problem.energy_needs();
+        for (index, agent) in problem.agents.iter().enumerate() {
+            let Some(goal_distances) = problem.goal_distances(agent) else {
+                break;
+            };
+            // A path of n_steps locations takes n_steps - 1 steps, and starts with a full battery
+            let energy = problem.energy.as_ref().map_or(0, |energy| energy.capacity);
+            let reachable = goal_distances.reachable(
+                &problem,
+                energy_needs.as_deref(),
+                (agent.start_y, agent.start_x),
+                agent.steps_left(1),
+                energy,
+            );
+            if !reachable {
+                bail!(
+                    "Agent {} can not reach the goal from ({}, {}) within {} steps and its battery",
+                    index,
+                    agent.start_y,
+                    agent.start_x,
+                    agent.steps_left(0)
+                );
+            }
+        }
+        Ok(problem)
+    }
+}
+
+impl WorldSource {
+    /// Returns the height and width of the world to generate, or None if the world is loaded from a file
+    pub fn size(&self) -> Option<(usize, usize)> {
+        match *self {
+            WorldSource::Random { height, width, .. }
+            | WorldSource::Perlin { height, width, .. }
+            | WorldSource::Noise { height, width, .. }
+            | WorldSource::Hotspots { height, width, .. }
+            | WorldSource::Gradient { height, width, .. }
+            | WorldSource::Corridors { height, width, .. }
+            | WorldSource::Maze { height, width, .. }
+            | WorldSource::Tiled { height, width, .. } => Some((height, width)),
+            WorldSource::File { .. } => None,
+        }
+    }
+}
+
+impl WorldSpec {
+    /// The obstacles and the costs are drawn from seeds offset from the seed of the world, so that they are not
+    /// correlated with the values of the world, nor with each other
+    const OBSTACLE_SEED_OFFSET: u64 = 2000;
+    const COST_SEED_OFFSET: u64 = 1000;
+
+    /// Creates the world, holding values of type T. Generators without a seed of their own use the given seed. The
+    /// random and noise generators generate values of type T directly. The other generators and world files only hold
+    /// i64 values, which are converted to the default bounds of T. Fails if the world has no locations, or if its
+    /// values can not be generated
+    pub fn build<T: CellValue>(&self, seed: u64) -> Result<World<T>> {
+        // Generators are not asked for worlds without locations
+        if let Some((height, width)) = self.source.size().filter(|&(h, w)| h == 0 || w == 0) {
+            bail!("World of {}x{} has no locations", height, width);
+        }
+
+        let mut world: World<T> = match self.source {
+            WorldSource::Random {
+                height,
+                width,
+                sparsity,
+                seed: world_seed,
+            } => World::random(height, width, sparsity, world_seed.unwrap_or(seed)),
+            WorldSource::Perlin {
+                height,
+                width,
+                sparsity,
+                seed: world_seed,
+            } => World::perlin(height, width, sparsity, world_seed.unwrap_or(seed))?,
+            WorldSource::Noise {
+                height,
+                width,
+                sparsity,
+                ref noise,
+                seed: world_seed,
+            } => World::noise(height, width, sparsity, noise, world_seed.unwrap_or(seed))?,
+            WorldSource::Hotspots {
+                height,
+                width,
+                n,
+                amplitude,
+                radius,
+                seed: world_seed,
+            } => convert_cells(World::hotspots(
+                height,
+                width,
+                n,
+                amplitude,
+                radius,
+                world_seed.unwrap_or(seed),
+            )),
+            WorldSource::Gradient {
+                height,
+                width,
+                angle,
+            } => convert_cells(World::gradient(height, width, angle)),
+            WorldSource::Corridors {
+                height,
+                width,
+                n,
+                corridor_width,
+                value,
+                seed: world_seed,
+            } => convert_cells(World::corridors(
+                height,
+                width,
+                n,
+                corridor_width,
+                value,
+                world_seed.unwrap_or(seed),
+            )),
+            WorldSource::Maze {
+                height,
+                width,
+                seed: world_seed,
+            } => convert_cells(World::maze(height, width, world_seed.unwrap_or(seed))),
+            WorldSource::Tiled {
+                height,
+                width,
+                scale,
+                sparsity,
+                ref noise,
+                origin: (y0, x0),
+                seed: world_seed,
+            } => convert_cells(
+                TiledWorld::noise(scale, sparsity, noise.clone(), world_seed.unwrap_or(seed))?
+                    .window(y0, x0, height, width),
+            ),
+            WorldSource::File { ref path } => convert_cells(World::load(path)?),
+        };
+
+        // A world file can hold a world without locations as well
+        if world.get_height() == 0 || world.get_width() == 0 {
+            bail!(
+                "World of {}x{} has no locations",
+                world.get_height(),
+                world.get_width()
+            );
+        }
+
+        let generated = !matches!(self.source, WorldSource::File { .. });
+        let world_seed = world.get_seed().unwrap_or(seed);
+        if generated && 0.0 < self.obstacles {
+            let obstacle_seed = world_seed.wrapping_add(Self::OBSTACLE_SEED_OFFSET);
+            world.fill_obstacles(self.obstacles, &mut StdRng::seed_from_u64(obstacle_seed));
+        }
+        if let Some(costs) = self.costs.as_ref().filter(|_| generated) {
+            let cost_seed = world_seed.wrapping_add(Self::COST_SEED_OFFSET);
+            world.fill_costs(
+                costs.max_cost,
+                &costs.noise,
+                &mut StdRng::seed_from_u64(cost_seed),
+            );
+        }
+        world.set_topology(self.topology);
+        Ok(world)
+    }
+}
+
+/// Converts a world of i64 values to a world of values of type T, rescaled to the default bounds of T. A world of i64
+/// values is returned as it is, so that it keeps its own bounds
+fn convert_cells<T: CellValue>(world: World) -> World<T> {
+    match (Box::new(world) as Box<dyn Any>).downcast::<World<T>>() {
+        Ok(world) => *world,
+        Err(world) => world
+            .downcast::<World>()
+            .unwrap()
+            .convert(T::LOWER, T::UPPER),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds the problem, and returns the message it fails with
+    fn build_error(spec: &ProblemSpec) -> String {
+        match spec.build::<i64>() {
+            Ok(_) => panic!("expected the problem to be rejected"),
+            Err(error) => error.to_string(),
+        }
+    }
+
+    fn maze() -> ProblemSpec {
+        ProblemSpec {
+            world: WorldSpec {
+                source: WorldSource::Maze {
+                    height: 21,
+                    width: 21,
+                    seed: Some(1),
+                },
+                ..ProblemSpec::default().world
+            },
+            seed: Some(1),
+            ..ProblemSpec::default()
+        }
+    }
+
+    #[test]
+    fn a_start_outside_of_the_world_is_rejected() {
+        let spec = ProblemSpec {
+            start: Some((500, 500)),
+            ..maze()
+        };
+        assert!(build_error(&spec).contains("outside of the world"));
+    }
+
+    #[test]
+    fn a_start_on_an_obstacle_is_rejected() {
+        // The border of a maze is a wall
+        let spec = ProblemSpec {
+            start: Some((0, 0)),
+            ..maze()
+        };
+        assert!(build_error(&spec).contains("on an obstacle"));
+        assert!(ProblemSpec {
+            start: Some((1, 1)),
+            ..maze()
+        }
+        .build::<i64>()
+        .is_ok());
+    }
+
+    #[test]
+    fn an_agent_without_steps_is_rejected() {
+        let spec = ProblemSpec {
+            n_steps: Some(0),
+            ..maze()
+        };
+        assert!(build_error(&spec).contains("no steps"));
+    }
+
+    #[test]
+    fn an_unreachable_goal_is_rejected() {
+        // The far corner of the maze is too many steps away
+        let spec = ProblemSpec {
+            start: Some((1, 1)),
+            n_steps: Some(5),
+            goal: Some(Goal::Region {
+                y0: 19,
+                y1: 20,
+                x0: 19,
+                x1: 20,
+            }),
+            ..maze()
+        };
+        assert!(build_error(&spec).contains("can not reach the goal"));
+        // The border of the maze is a wall, so a region on it has no location to end at
+        let spec = ProblemSpec {
+            start: Some((1, 1)),
+            goal: Some(Goal::Region {
+                y0: 0,
+                y1: 1,
+                x0: 0,
+                x1: 21,
+            }),
+            ..maze()
+        };
+        assert!(build_error(&spec).contains("can not reach the goal"));
+        // Returning to the start is always possible
+        assert!(ProblemSpec {
+            start: Some((1, 1)),
+            goal: Some(Goal::Start),
+            ..maze()
+        }
+        .build::<i64>()
+        .is_ok());
+    }
+
+    #[test]
+    fn a_battery_only_agent_needs_a_draining_battery() {
+        let battery = |orthogonal: i64| {
+            Some(EnergyModel {
+                capacity: 20,
+                move_energy: MoveCost {
+                    orthogonal,
+                    diagonal: 3,
+                },
+                stations: vec![(1, 1)],
+            })
+        };
+        let spec = ProblemSpec {
+            start: Some((1, 1)),
+            energy: battery(2),
+            ..maze()
+        };
+        let problem = spec.build::<i64>().unwrap();
+        assert_eq!(problem.agents[0].n_steps, None);
+        let spec = ProblemSpec {
+            energy: battery(0),
+            ..spec
+        };
+        assert!(build_error(&spec).contains("steps can use 0 energy"));
+        let spec = ProblemSpec {
+            energy: battery(2),
+            goal: Some(Goal::Start),
+            ..spec
+        };
+        assert!(build_error(&spec).contains("needs a limit on its steps"));
+    }
+
+    #[test]
+    fn a_world_without_locations_is_rejected_before_it_is_generated() {
+        let sources = [
+            WorldSource::Random {
+                height: 0,
+                width: 10,
+                sparsity: 0.5,
+                seed: None,
+            },
+            WorldSource::Perlin {
+                height: 10,
+                width: 0,
+                sparsity: 0.5,
+                seed: None,
+            },
+            WorldSource::Hotspots {
+                height: 0,
+                width: 10,
+                n: 3,
+                amplitude: 50,
+                radius: 2.0,
+                seed: None,
+            },
+            WorldSource::Corridors {
+                height: 10,
+                width: 0,
+                n: 2,
+                corridor_width: 1.0,
+                value: 50,
+                seed: None,
+            },
+            WorldSource::Maze {
+                height: 0,
+                width: 0,
+                seed: None,
+            },
+        ];
+        for source in sources {
+            let spec = ProblemSpec {
+                world: WorldSpec {
+                    source,
+                    ..maze().world
+                },
+                ..maze()
+            };
+            assert!(build_error(&spec).contains("has no locations"));
+        }
+    }
+}
@@ -1,44 +1,136 @@
-use std::{
-    collections::HashMap,
-    sync::{
-        atomic::{AtomicI64, Ordering},
-        Mutex,
-    },
-};
+use std::{collections::HashMap, path::Path, sync::Mutex};
+
+use color_eyre::eyre::Result;
+use serde::{Deserialize, Serialize};
 
-use super::colors::Color;
+use super::{
+    colors::Color,
+    files,
+    problem::Problem,
+    problem_spec::{CellType, ProblemSpec},
+};
+use crate::world::{
+    cell::{CellSum, CellValue},
+    journal::Journal,
+};
 
 /// This struct is used to store the best solution found so far
 /// It is also used to store the current progress of any algorithm
 /// The struct is thread safe, and mulitple algorithms can access it concurrently
-#[derive(Default)]
-pub struct Solution {
-    pub path: Mutex<Vec<(i64, i64)>>,
-    pub score: AtomicI64,
+/// Submitted paths are validated against the problem, so that paths crossing obstacles or with a wrong score are never accepted
+/// A solution holds a path for every agent of the problem, and the joint score of these paths
+pub struct Solution<T: CellValue = i64> {
+    problem: Problem<T>,
+    pub paths: Mutex<Vec<Vec<(i64, i64)>>>,
+    pub score: Mutex<T::Sum>,
     pub paths_in_progress: Mutex<HashMap<Color, Vec<(i64, i64)>>>,
+    /// The visits made by the attempt that found the best paths
+    pub journal: Mutex<Journal<T>>,
 }
 
-impl Solution {
-    pub fn get_score(&self) -> i64 {
-        self.score.load(Ordering::SeqCst)
+impl<T: CellValue> Solution<T> {
+    pub fn new(problem: Problem<T>) -> Solution<T> {
+        Solution {
+            problem,
+            paths: Mutex::default(),
+            // Net scores can be negative, so any first path is an improvement
+            score: Mutex::new(T::Sum::MIN),
+            paths_in_progress: Mutex::default(),
+            journal: Mutex::default(),
+        }
+    }
+
+    pub fn get_score(&self) -> T::Sum {
+        *self.score.lock().unwrap()
     }
 
-    pub fn set_score(&self, score: i64) {
-        self.score.store(score, Ordering::SeqCst);
+    pub fn set_score(&self, score: T::Sum) {
+        *self.score.lock().unwrap() = score;
     }
 
-    pub fn submit_path(&self, path: &Vec<(i64, i64)>, score: i64) -> bool {
+    /// Submits the paths of an attempt, together with the journal of its visits. Returns whether the paths are the
+    /// best so far, in which case they and the journal are kept
+    pub fn submit_paths(
+        &self,
+        paths: &[Vec<(i64, i64)>],
+        score: T::Sum,
+        journal: &Journal<T>,
+    ) -> bool {
+        if let Err(error) = self.problem.validate_paths(paths) {
+            tracing::warn!("Rejected paths with score {}: {}", score, error);
+            return false;
+        }
+        // Every algorithm is scored against the harvest model of the problem
+        let actual_score = self.problem.score_paths(paths);
+        if actual_score != score {
+            tracing::warn!(
+                "Rejected paths with score {}, while they actually score {}",
+                score,
+                actual_score
+            );
+            return false;
+        }
+
         let new_highscore = self.get_score() <= score;
         if new_highscore {
-            let mut data = self.path.lock().unwrap();
-            *data = path.clone();
+            let mut data = self.paths.lock().unwrap();
+            *data = paths.to_vec();
+            *self.journal.lock().unwrap() = journal.clone();
             self.set_score(score);
         }
         new_highscore
     }
 
-    pub fn submit_path_in_progress(&self, color: Color, path: &Vec<(i64, i64)>) {
+    pub fn submit_path_in_progress(&self, color: Color, path: &[(i64, i64)]) {
         let mut data = self.paths_in_progress.lock().unwrap();
-        data.insert(color, path.clone());
+        data.insert(color, path.to_vec());
+    }
+}
+
+/// A solution as written to a TOML or JSON file, together with the problem it solves, so that it can be replayed
+/// and scored later. The seed and the start location of the problem are always filled in. The score is of the type in
+/// which the values of the world of the problem are summed
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SavedSolution<S = i64> {
+    pub problem: ProblemSpec,
+    pub score: S,
+    /// The path of every agent
+    pub paths: Vec<Vec<(i64, i64)>>,
+}
+
+impl<S: CellSum> SavedSolution<S> {
+    /// Captures the best paths found so far for a problem that was built from the given problem spec
+    pub fn new<T: CellValue<Sum = S>>(
+        spec: &ProblemSpec,
+        solution: &Solution<T>,
+    ) -> SavedSolution<S> {
+        SavedSolution {
+            problem: spec.resolved(&solution.problem),
+            score: solution.get_score(),
+            paths: solution.paths.lock().unwrap().clone(),
+        }
+    }
+
+    /// Loads a solution from a TOML or JSON file. The format is derived from the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<SavedSolution<S>> {
+        files::load(path.as_ref())
+    }
+
+    /// Saves the solution to a TOML or JSON file. The format is derived from the file extension
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        files::save(path.as_ref(), self)
+    }
+}
+
+impl SavedSolution {
+    /// Returns the cell type of the world of the problem of a solution file, which decides the type of its score
+    pub fn cell_type(path: impl AsRef<Path>) -> Result<CellType> {
+        /// A solution file of which only the problem is read
+        #[derive(Deserialize)]
+        struct Header {
+            problem: ProblemSpec,
+        }
+        let header: Header = files::load(path.as_ref())?;
+        Ok(header.problem.world.cell_type)
     }
 }
@@ -1,20 +1,20 @@
 use std::{sync::Arc, thread};
 
 use derive_more::Constructor;
-use minifb::{Key, Window, WindowOptions};
+use minifb::{Key, KeyRepeat, Window, WindowOptions};
 
 use crate::{
-    data_structures::{problem::Problem, solution::Solution},
-    world::World,
+    data_structures::{colors::Color, problem::Problem, solution::Solution},
+    world::{cell::CellValue, islands::Islands, Landscape},
 };
 
 #[derive(Constructor)]
-pub struct MyWindow {
-    problem: Problem,
-    solution: Arc<Solution>,
+pub struct MyWindow<T: CellValue = i64> {
+    problem: Problem<T>,
+    solution: Arc<Solution<T>>,
 }
 
-impl MyWindow {
+impl<T: CellValue> MyWindow<T> {
     const MAX_WIDTH: usize = 1000;
     const MAX_HEIGHT: usize = 1000;
 
@@ -25,8 +25,10 @@ impl MyWindow {
     }
 
     fn run_window(&self, fps: usize) {
-        let width = self.problem.world.get_grid_size();
-        let height = self.problem.world.get_grid_size();
+        let width = self.problem.world.get_width();
+        let height = self.problem.world.get_height();
+        let (lower_bound, upper_bound) = self.problem.world.get_bounds();
+        let (lower_bound, upper_bound) = (lower_bound.to_f64(), upper_bound.to_f64());
 
         if width > Self::MAX_WIDTH || height > Self::MAX_HEIGHT {
             tracing::error!("Grid too large for visualizer");
@@ -65,14 +67,29 @@ impl MyWindow {
         // Prepare a buffer to store pixel data (ARGB or XRGB)
         let mut buffer: Vec<u32> = vec![0; width * height];
 
+        // Press I to toggle the outlines of the islands
+        let mut islands: Option<Islands<T::Sum>> = None;
+
         // Continuously update the window
         while window.is_open() && !window.is_key_down(Key::Escape) {
+            if window.is_key_pressed(Key::I, KeyRepeat::No) {
+                islands = match islands {
+                    Some(_) => None,
+                    None => Some(self.problem.world.islands(T::default())),
+                };
+            }
+
             // Update the buffer for the grid
             for y in 0..height {
                 for x in 0..width {
-                    let value = self.problem.world.at(y as i64, x as i64).unwrap() as f64;
-                    let value_norm = (value - World::LOWER_BOUND as f64)
-                        / ((World::UPPER_BOUND - World::LOWER_BOUND) as f64);
+                    // Obstacles are drawn in dark red, so that they stand out from empty locations
+                    if self.problem.world.is_blocked(y as i64, x as i64) {
+                        buffer[y * width + x] = Color::DARK_RED.0;
+                        continue;
+                    }
+
+                    let value = self.problem.world.at(y as i64, x as i64).unwrap().to_f64();
+                    let value_norm = (value - lower_bound) / (upper_bound - lower_bound);
 
                     // 8 bit RGB [0..255] Grayscale
                     let r: u32 = ((value_norm * 255.0) as u32) << 16;
@@ -83,30 +100,59 @@ impl MyWindow {
                 }
             }
 
-            /* Plot all paths that are currently in progress */
+            /* Plot the coasts of all islands, each island in its own color */
+            if let Some(islands) = &islands {
+                for island in islands.islands() {
+                    let color = &Color::ISLANDS[island.label % Color::ISLANDS.len()];
+                    for &(y, x) in island.coast.iter() {
+                        buffer[y as usize * width + x as usize] = color.0;
+                    }
+                }
+            }
+
+            /* Plot all paths that are currently in progress. Paths on a toroidal world are wrapped around its edges */
             let paths_in_progress = self.solution.paths_in_progress.lock().unwrap();
             for (color, path) in paths_in_progress.iter() {
                 // Create a color from the hash of the name
                 for &(y, x) in path.iter() {
+                    let (y, x) = self.problem.world.wrap(y, x);
                     buffer[y as usize * width + x as usize] = color.0;
                 }
             }
 
-            /* Plot the current best path */
-            let path = self.solution.path.lock().unwrap();
-            if 0 < path.len() {
-                // Plot the entire path in blue
+            /* Plot the current best path of every agent */
+            let paths = self.solution.paths.lock().unwrap();
+            for (agent, path) in paths
+                .iter()
+                .enumerate()
+                .filter(|(_, path)| !path.is_empty())
+            {
+                // Plot the entire path in the color of the agent
+                let color = &Color::AGENTS[agent % Color::AGENTS.len()];
                 for &(y, x) in path.iter() {
-                    buffer[y as usize * width + x as usize] = 0x0000FF;
+                    let (y, x) = self.problem.world.wrap(y, x);
+                    buffer[y as usize * width + x as usize] = color.0;
                 }
                 // Plot the beginning in green
                 let (y, x) = path[0];
+                let (y, x) = self.problem.world.wrap(y, x);
                 buffer[y as usize * width + x as usize] = 0x00FF00;
                 // Plot the end in red
                 let (y, x) = path[path.len() - 1];
+                let (y, x) = self.problem.world.wrap(y, x);
                 buffer[y as usize * width + x as usize] = 0xFF0000;
             }
 
+            /* Plot the stations at which batteries are recharged in lime */
+            if let Some(energy) = &self.problem.energy {
+                for &(y, x) in energy.stations.iter() {
+                    if self.problem.world.in_world(y, x) {
+                        let (y, x) = self.problem.world.wrap(y, x);
+                        buffer[y as usize * width + x as usize] = Color::LIME.0;
+                    }
+                }
+            }
+
             // Render the updated buffer
             window.update_with_buffer(&buffer, width, height).unwrap();
         }
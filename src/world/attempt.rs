@@ -0,0 +1,97 @@
+use super::{
+    cell::CellValue,
+    journal::Journal,
+    overlay::VisitOverlay,
+    regenerating::{RegeneratingWorld, Regeneration},
+    Landscape,
+};
+
+/// The view of the world of a problem in which a single attempt of an algorithm visits locations. Visited locations
+/// either stay harvested for the rest of the attempt, or regenerate over time if the problem says so
+#[derive(Clone)]
+pub enum AttemptWorld<T: CellValue = i64> {
+    Overlay(VisitOverlay<T>),
+    Regenerating(RegeneratingWorld<Regeneration, T>),
+}
+
+impl<T: CellValue> AttemptWorld<T> {
+    /// Factor of the pyramid of the world, whose levels are searched coarse to fine
+    pub const PYRAMID_FACTOR: usize = 3;
+
+    /// Visits a location on behalf of an algorithm at timestep t, and returns the value that was collected
+    pub fn visit_by(&mut self, y: i64, x: i64, t: u64, algorithm: &'static str) -> Option<T> {
+        match self {
+            AttemptWorld::Overlay(world) => world.visit_by(y, x, t, algorithm),
+            AttemptWorld::Regenerating(world) => world.visit_by(y, x, t, algorithm),
+        }
+    }
+
+    /// Returns the sum of the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1
+    pub fn block_sum(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> T::Sum {
+        match self {
+            AttemptWorld::Overlay(world) => world.block_sum(y0, y1, x0, x1),
+            AttemptWorld::Regenerating(world) => world.block_sum(y0, y1, x0, x1),
+        }
+    }
+
+    /// Returns the sum of a location in the given level of the pyramid of the world, being the block of
+    /// PYRAMID_FACTOR^level by PYRAMID_FACTOR^level locations it covers. Falls back to the block sum if the world has
+    /// no pyramid, for locations outside of the level, such as across the edge of a toroidal world, and for regenerating
+    /// worlds, whose values change over time
+    pub fn level_sum(&self, level: usize, y: i64, x: i64) -> T::Sum {
+        let sum = match self {
+            AttemptWorld::Overlay(world) => world.pyramid_at(Self::PYRAMID_FACTOR, level, y, x),
+            AttemptWorld::Regenerating(_) => None,
+        };
+        sum.unwrap_or_else(|| {
+            let size = Self::PYRAMID_FACTOR.pow(level as u32) as i64;
+            self.block_sum(y * size, (y + 1) * size, x * size, (x + 1) * size)
+        })
+    }
+
+    pub fn get_journal(&self) -> &Journal<T> {
+        match self {
+            AttemptWorld::Overlay(world) => world.get_journal(),
+            AttemptWorld::Regenerating(world) => world.get_journal(),
+        }
+    }
+}
+
+impl<T: CellValue> Landscape for AttemptWorld<T> {
+    type Value = T;
+
+    fn at(&self, y: i64, x: i64) -> Option<T> {
+        match self {
+            AttemptWorld::Overlay(world) => world.at(y, x),
+            AttemptWorld::Regenerating(world) => Landscape::at(world, y, x),
+        }
+    }
+
+    fn set(&mut self, y: i64, x: i64, value: T) {
+        match self {
+            AttemptWorld::Overlay(world) => world.set(y, x, value),
+            AttemptWorld::Regenerating(world) => world.set(y, x, value),
+        }
+    }
+
+    fn in_world(&self, y: i64, x: i64) -> bool {
+        match self {
+            AttemptWorld::Overlay(world) => world.in_world(y, x),
+            AttemptWorld::Regenerating(world) => world.in_world(y, x),
+        }
+    }
+
+    fn is_passable(&self, y: i64, x: i64) -> bool {
+        match self {
+            AttemptWorld::Overlay(world) => world.is_passable(y, x),
+            AttemptWorld::Regenerating(world) => world.is_passable(y, x),
+        }
+    }
+
+    fn visit(&mut self, y: i64, x: i64) -> Option<T> {
+        match self {
+            AttemptWorld::Overlay(world) => world.visit(y, x),
+            AttemptWorld::Regenerating(world) => Landscape::visit(world, y, x),
+        }
+    }
+}
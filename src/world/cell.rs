@@ -0,0 +1,108 @@
+use std::{
+    error::Error,
+    fmt::{Debug, Display},
+    iter::Sum,
+    ops::{Add, AddAssign, Sub},
+    str::FromStr,
+};
+
+use rand::distr::uniform::SampleUniform;
+use serde::{de::DeserializeOwned, Serialize};
+
+/// The types a location of a world can hold. Integer types model discrete values, while floating point types model
+/// fractional values, such as probabilities, without quantisation
+pub trait CellValue:
+    Copy
+    + Default
+    + PartialOrd
+    + Debug
+    + Display
+    + FromStr<Err: Error + Send + Sync + 'static>
+    + SampleUniform
+    + Send
+    + Sync
+    + 'static
+{
+    /// The type in which values are summed, wide enough that sums of many values do not overflow
+    type Sum: CellSum + From<Self>;
+
+    /// Default bounds of new worlds
+    const LOWER: Self;
+    const UPPER: Self;
+
+    fn to_f64(self) -> f64;
+
+    /// Converts from f64. Integer types truncate towards zero and saturate at their limits
+    fn from_f64(value: f64) -> Self;
+}
+
+/// The types in which values of a world are summed. Sums are written to files as scores
+pub trait CellSum:
+    Copy
+    + Default
+    + PartialOrd
+    + Debug
+    + Display
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + AddAssign
+    + Sum
+    + Serialize
+    + DeserializeOwned
+    + Send
+    + Sync
+    + 'static
+{
+    /// Lower than any sum, so that any sum is an improvement on it
+    const MIN: Self;
+
+    /// Converts from i64, such as the cost of a path. Floating point sums round to the nearest value
+    fn from_i64(value: i64) -> Self;
+
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! cell_value {
+    ($type:ty, $sum:ty, $lower:expr, $upper:expr) => {
+        impl CellValue for $type {
+            type Sum = $sum;
+
+            const LOWER: Self = $lower;
+            const UPPER: Self = $upper;
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+
+            fn from_f64(value: f64) -> Self {
+                value as $type
+            }
+        }
+    };
+}
+
+cell_value!(u8, i64, 0, u8::MAX);
+cell_value!(u16, i64, 0, u16::MAX);
+cell_value!(i32, i64, 0, 100);
+cell_value!(i64, i64, 0, 100);
+cell_value!(f32, f64, 0.0, 1.0);
+cell_value!(f64, f64, 0.0, 1.0);
+
+macro_rules! cell_sum {
+    ($type:ty, $min:expr) => {
+        impl CellSum for $type {
+            const MIN: Self = $min;
+
+            fn from_i64(value: i64) -> Self {
+                value as $type
+            }
+
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        }
+    };
+}
+
+cell_sum!(i64, i64::MIN);
+cell_sum!(f64, f64::NEG_INFINITY);
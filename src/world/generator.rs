@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+
+use noise::{
+    Billow, Fbm, MultiFractal, NoiseFn, Perlin, RidgedMulti, ScalePoint, Seedable, Simplex, Worley,
+};
+
+/// The basic noise functions a world can be generated from
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseSource {
+    Perlin,
+    Simplex,
+    /// Cellular noise
+    Worley,
+}
+
+/// The ways in which multiple octaves of a noise source can be combined
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoiseKind {
+    /// A single octave of the noise source. Only the frequency is used
+    Plain,
+    /// Fractal brownian motion. Gives smooth, rolling islands
+    Fbm,
+    /// Like Fbm, but with the absolute value of each octave. Gives puffy, cloud-like islands
+    Billow,
+    /// Gives sharp ridges. The attenuation determines how quickly ridges get smaller with each octave
+    RidgedMulti,
+}
+
+/// Warps the coordinates at which the noise is sampled with another noise function, which twists and stretches the
+/// shapes of the islands
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DomainWarp {
+    /// Maximum displacement of the coordinates, in units of noise
+    pub strength: f64,
+    pub frequency: f64,
+}
+
+/// Configuration of the noise a world is generated from. The default configuration is the fractal perlin noise the
+/// worlds have always been generated with. Fields that are left out when deserialising take their default value
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NoiseConfig {
+    pub source: NoiseSource,
+    pub kind: NoiseKind,
+    pub octaves: usize,
+    pub frequency: f64,
+    pub lacunarity: f64,
+    pub persistence: f64,
+    /// Only used by RidgedMulti
+    pub attenuation: f64,
+    pub warp: Option<DomainWarp>,
+}
+
+impl Default for NoiseConfig {
+    fn default() -> Self {
+        NoiseConfig {
+            source: NoiseSource::Perlin,
+            kind: NoiseKind::Fbm,
+            octaves: 4,
+            frequency: 2.0,
+            lacunarity: 5.0,
+            persistence: 1.0,
+            attenuation: 2.0,
+            warp: None,
+        }
+    }
+}
+
+impl NoiseConfig {
+    /// Seeds of the domain warp are offset from the seed of the noise, so that the warp is not correlated with it
+    const WARP_SEED_OFFSET: u32 = 1000;
+
+    /// Creates the noise function described by this configuration. The noise function is not thread safe, since the
+    /// Worley noise source is not
+    pub fn build(&self, seed: u32) -> Box<dyn NoiseFn<f64, 2>> {
+        let noise = match self.source {
+            NoiseSource::Perlin => self.combine::<Perlin>(seed),
+            NoiseSource::Simplex => self.combine::<Simplex>(seed),
+            NoiseSource::Worley => self.combine::<Worley>(seed),
+        };
+
+        match self.warp {
+            Some(warp) => {
+                let warp_noise = |offset: u32| {
+                    Fbm::<Perlin>::new(seed.wrapping_add(offset)).set_frequency(warp.frequency)
+                };
+                Box::new(Warped {
+                    noise,
+                    warp_y: warp_noise(Self::WARP_SEED_OFFSET),
+                    warp_x: warp_noise(2 * Self::WARP_SEED_OFFSET),
+                    strength: warp.strength,
+                })
+            }
+            None => noise,
+        }
+    }
+
+    /// Combines octaves of the given noise source into a single noise function
+    fn combine<T>(&self, seed: u32) -> Box<dyn NoiseFn<f64, 2>>
+    where
+        T: Default + Seedable + NoiseFn<f64, 2> + 'static,
+    {
+        match self.kind {
+            NoiseKind::Plain => {
+                Box::new(ScalePoint::new(T::default().set_seed(seed)).set_scale(self.frequency))
+            }
+            NoiseKind::Fbm => Box::new(
+                Fbm::<T>::new(seed)
+                    .set_octaves(self.octaves)
+                    .set_frequency(self.frequency)
+                    .set_lacunarity(self.lacunarity)
+                    .set_persistence(self.persistence),
+            ),
+            NoiseKind::Billow => Box::new(
+                Billow::<T>::new(seed)
+                    .set_octaves(self.octaves)
+                    .set_frequency(self.frequency)
+                    .set_lacunarity(self.lacunarity)
+                    .set_persistence(self.persistence),
+            ),
+            NoiseKind::RidgedMulti => Box::new(
+                RidgedMulti::<T>::new(seed)
+                    .set_octaves(self.octaves)
+                    .set_frequency(self.frequency)
+                    .set_lacunarity(self.lacunarity)
+                    .set_persistence(self.persistence)
+                    .set_attenuation(self.attenuation),
+            ),
+        }
+    }
+}
+
+/// A noise function whose coordinates are displaced by two other noise functions
+struct Warped {
+    noise: Box<dyn NoiseFn<f64, 2>>,
+    warp_y: Fbm<Perlin>,
+    warp_x: Fbm<Perlin>,
+    strength: f64,
+}
+
+impl NoiseFn<f64, 2> for Warped {
+    fn get(&self, [x, y]: [f64; 2]) -> f64 {
+        let dx = self.strength * self.warp_x.get([x, y]);
+        let dy = self.strength * self.warp_y.get([x, y]);
+        self.noise.get([x + dx, y + dy])
+    }
+}
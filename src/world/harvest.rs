@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+use super::cell::CellValue;
+
+/// Determines how much of the value of a location is collected by a visit, and how much remains for later visits
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HarvestModel {
+    /// A visit collects the entire value, and empties the location
+    #[default]
+    Full,
+    /// A visit collects the given fraction of the remaining value
+    /// fraction: 0.0 - 1.0
+    Fraction(f64),
+    /// A visit collects at most the given amount
+    Amount(f64),
+    /// Only the given number of visits are rewarded. Each of them collects the entire value, after which the location
+    /// is empty
+    Capped(u32),
+}
+
+impl HarvestModel {
+    /// Given the value of a location and the number of times it has been visited before, returns the value collected
+    /// by this visit and the value that remains
+    pub fn harvest<T: CellValue>(&self, value: T, visits: u32) -> (T, T) {
+        match *self {
+            HarvestModel::Full => (value, T::default()),
+            HarvestModel::Fraction(fraction) => {
+                let collected = T::from_f64(value.to_f64() * fraction.clamp(0.0, 1.0));
+                (collected, T::from_f64(value.to_f64() - collected.to_f64()))
+            }
+            HarvestModel::Amount(amount) => {
+                let collected = T::from_f64(f64::min(value.to_f64(), amount));
+                (collected, T::from_f64(value.to_f64() - collected.to_f64()))
+            }
+            HarvestModel::Capped(n) => match (visits < n, visits + 1 < n) {
+                (true, true) => (value, value),
+                (true, false) => (value, T::default()),
+                _ => (T::default(), T::default()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn harvest_on_i64() {
+        // (model, value, visits before, collected, remaining)
+        let cases = [
+            (HarvestModel::Full, 37, 0, 37, 0),
+            (HarvestModel::Full, 37, 4, 37, 0),
+            // Integer values are truncated, and the rest remains
+            (HarvestModel::Fraction(0.5), 37, 0, 18, 19),
+            (HarvestModel::Fraction(0.3), 10, 2, 3, 7),
+            (HarvestModel::Fraction(0.1), 5, 0, 0, 5),
+            (HarvestModel::Fraction(1.5), 10, 0, 10, 0),
+            (HarvestModel::Fraction(-0.5), 10, 0, 0, 10),
+            (HarvestModel::Amount(10.0), 37, 0, 10, 27),
+            (HarvestModel::Amount(10.0), 7, 3, 7, 0),
+            (HarvestModel::Amount(2.5), 7, 0, 2, 5),
+            // Every rewarded visit collects the entire value, and the last one empties the location
+            (HarvestModel::Capped(3), 37, 0, 37, 37),
+            (HarvestModel::Capped(3), 37, 1, 37, 37),
+            (HarvestModel::Capped(3), 37, 2, 37, 0),
+            (HarvestModel::Capped(3), 37, 3, 0, 0),
+            (HarvestModel::Capped(1), 37, 0, 37, 0),
+            (HarvestModel::Capped(0), 37, 0, 0, 0),
+        ];
+        for (model, value, visits, collected, remaining) in cases {
+            assert_eq!(
+                model.harvest(value, visits),
+                (collected, remaining),
+                "{:?} of {} after {} visits",
+                model,
+                value,
+                visits
+            );
+        }
+    }
+
+    #[test]
+    fn harvest_on_f64() {
+        let cases = [
+            (HarvestModel::Full, 0.8, 0, 0.8, 0.0),
+            (
+                HarvestModel::Fraction(0.25),
+                0.8,
+                0,
+                0.2,
+                0.6000000000000001,
+            ),
+            (HarvestModel::Amount(0.5), 0.8, 0, 0.5, 0.8 - 0.5),
+            (HarvestModel::Amount(0.5), 0.3, 1, 0.3, 0.0),
+            (HarvestModel::Capped(2), 0.8, 1, 0.8, 0.0),
+        ];
+        for (model, value, visits, collected, remaining) in cases {
+            assert_eq!(
+                model.harvest(value, visits),
+                (collected, remaining),
+                "{:?} of {} after {} visits",
+                model,
+                value,
+                visits
+            );
+        }
+    }
+}
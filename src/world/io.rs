@@ -0,0 +1,580 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Read, Write},
+    path::Path,
+};
+
+use color_eyre::eyre::{bail, eyre, Result};
+use ndarray::Array2;
+
+use super::World;
+
+/// The file formats a world can be loaded from and saved to. The format is derived from the file extension
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WorldFormat {
+    /// Netpbm grayscale image, 8 or 16 bit, ASCII (P2) or binary (P5). Can not hold obstacles
+    Pgm,
+    /// Grayscale PNG image, 8 or 16 bit. Fully transparent pixels are obstacles
+    Png,
+    /// A matrix of comma separated values, one row of the world per line. Obstacles are written as #
+    Csv,
+    /// Compact binary format, with a header carrying the dimensions, bounds and seed of the world, followed by the
+    /// values, the obstacles and the costs
+    Binary,
+}
+
+impl WorldFormat {
+    pub fn from_path(path: &Path) -> Result<WorldFormat> {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        match extension.as_deref() {
+            Some("pgm") => Ok(WorldFormat::Pgm),
+            Some("png") => Ok(WorldFormat::Png),
+            Some("csv") => Ok(WorldFormat::Csv),
+            Some("world") => Ok(WorldFormat::Binary),
+            _ => Err(eyre!("Unknown world file format: {}", path.display())),
+        }
+    }
+}
+
+/// This impl block contains everything needed to load worlds from files and save them to files. Images are
+/// interpreted as value maps, where black is the lower bound and white is the upper bound of the world. Only i64 worlds
+/// can be loaded and saved, other worlds are converted first
+impl World {
+    const MAGIC: &'static [u8; 4] = b"AVLR";
+    const VERSION: u8 = 3;
+    const OBSTACLE: &'static str = "#";
+
+    /// Loads a world from a file. The format is derived from the file extension
+    pub fn load(path: impl AsRef<Path>) -> Result<World> {
+        let path = path.as_ref();
+        let mut bytes = Vec::new();
+        File::open(path)
+            .map_err(|e| eyre!("Could not open {}: {}", path.display(), e))?
+            .read_to_end(&mut bytes)?;
+
+        let world = match WorldFormat::from_path(path)? {
+            WorldFormat::Pgm => Self::from_pgm(&bytes),
+            WorldFormat::Png => Self::from_png(&bytes),
+            WorldFormat::Csv => Self::from_csv(&String::from_utf8(bytes)?),
+            WorldFormat::Binary => Self::from_binary(&bytes),
+        }?;
+
+        tracing::info!(
+            "Loaded {}x{} world from {}",
+            world.height,
+            world.width,
+            path.display()
+        );
+        Ok(world)
+    }
+
+    /// Saves the world to a file. The format is derived from the file extension
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let bytes = match WorldFormat::from_path(path)? {
+            WorldFormat::Pgm => self.to_pgm(),
+            WorldFormat::Png => self.to_png()?,
+            WorldFormat::Csv => self.to_csv().into_bytes(),
+            WorldFormat::Binary => self.to_binary()?,
+        };
+
+        let mut writer = BufWriter::new(
+            File::create(path).map_err(|e| eyre!("Could not create {}: {}", path.display(), e))?,
+        );
+        writer.write_all(&bytes)?;
+        Ok(())
+    }
+
+    /// Creates a world from a matrix of values, mapping the range [0, max_value] onto the bounds of the world
+    fn from_gray(height: usize, width: usize, gray: Vec<u16>, max_value: u16) -> Result<World> {
+        if max_value == 0 {
+            bail!("Maximum gray value must be positive");
+        }
+        let information = Array2::from_shape_vec((height, width), gray)?.mapv(|v| {
+            let value_norm = v.min(max_value) as f64 / max_value as f64;
+            Self::LOWER_BOUND
+                + (value_norm * (Self::UPPER_BOUND - Self::LOWER_BOUND) as f64).round() as i64
+        });
+
+        let mut world = World::new(height, width);
+        world.information = information;
+        Ok(world)
+    }
+
+    /// Maps the values of the world onto the range [0, max_value]
+    fn to_gray(&self, max_value: u16) -> Vec<u16> {
+        let (lower_bound, upper_bound) = (self.lower_bound, self.upper_bound);
+        self.information
+            .iter()
+            .map(|&v| {
+                let value_norm = (v.clamp(lower_bound, upper_bound) - lower_bound) as f64
+                    / (upper_bound - lower_bound).max(1) as f64;
+                (value_norm * max_value as f64).round() as u16
+            })
+            .collect()
+    }
+
+    fn from_pgm(bytes: &[u8]) -> Result<World> {
+        // The header consists of the magic number, width, height and maximum value, separated by whitespace.
+        // Comments start with a # and run until the end of the line
+        let mut tokens = Vec::with_capacity(4);
+        let mut at = 0;
+        while tokens.len() < 4 {
+            match bytes.get(at) {
+                None => bail!("Unexpected end of PGM header"),
+                Some(b'#') => {
+                    while at < bytes.len() && bytes[at] != b'\n' {
+                        at += 1;
+                    }
+                }
+                Some(c) if c.is_ascii_whitespace() => at += 1,
+                Some(_) => {
+                    let start = at;
+                    while at < bytes.len() && !bytes[at].is_ascii_whitespace() {
+                        at += 1;
+                    }
+                    tokens.push(std::str::from_utf8(&bytes[start..at])?);
+                }
+            }
+        }
+        // A single whitespace character separates the header from the data
+        at += 1;
+
+        let width: usize = tokens[1].parse()?;
+        let height: usize = tokens[2].parse()?;
+        let max_value: u16 = tokens[3].parse()?;
+
+        let gray: Vec<u16> = match tokens[0] {
+            "P2" => std::str::from_utf8(bytes.get(at..).unwrap_or_default())?
+                .split_ascii_whitespace()
+                .map(|v| v.parse())
+                .collect::<Result<_, _>>()?,
+            "P5" if max_value < 256 => bytes
+                .get(at..)
+                .unwrap_or_default()
+                .iter()
+                .map(|&v| v as u16)
+                .collect(),
+            "P5" => bytes
+                .get(at..)
+                .unwrap_or_default()
+                .chunks_exact(2)
+                .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                .collect(),
+            magic => bail!("Unsupported PGM type {}, expected P2 or P5", magic),
+        };
+
+        check_size(height, width, gray.len(), "PGM")?;
+        Self::from_gray(height, width, gray, max_value)
+    }
+
+    /// Saves the world as binary PGM. The maximum value equals the range of the world, so that no information is lost
+    fn to_pgm(&self) -> Vec<u8> {
+        if self.obstacles.iter().any(|&blocked| blocked) {
+            tracing::warn!("PGM can not hold obstacles. The obstacles of the world are not saved");
+        }
+
+        let max_value = (self.upper_bound - self.lower_bound).clamp(1, u16::MAX as i64) as u16;
+        let mut bytes = format!("P5\n{} {}\n{}\n", self.width, self.height, max_value).into_bytes();
+        for v in self.to_gray(max_value) {
+            if max_value < 256 {
+                bytes.push(v as u8);
+            } else {
+                bytes.extend_from_slice(&v.to_be_bytes());
+            }
+        }
+        bytes
+    }
+
+    fn from_png(bytes: &[u8]) -> Result<World> {
+        let mut reader = png::Decoder::new(bytes).read_info()?;
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buffer)?;
+
+        // Grayscale images with an alpha channel interleave the gray and alpha values
+        let channels = match info.color_type {
+            png::ColorType::Grayscale => 1,
+            png::ColorType::GrayscaleAlpha => 2,
+            color_type => bail!(
+                "Only grayscale PNG images are supported, found {:?}",
+                color_type
+            ),
+        };
+        let (height, width) = (info.height as usize, info.width as usize);
+        let buffer = &buffer[..info.buffer_size()];
+
+        let (samples, max_value): (Vec<u16>, u16) = match info.bit_depth {
+            png::BitDepth::Eight => (buffer.iter().map(|&v| v as u16).collect(), u8::MAX as u16),
+            png::BitDepth::Sixteen => (
+                buffer
+                    .chunks_exact(2)
+                    .map(|v| u16::from_be_bytes([v[0], v[1]]))
+                    .collect(),
+                u16::MAX,
+            ),
+            depth => bail!(
+                "Only 8 and 16 bit PNG images are supported, found {:?}",
+                depth
+            ),
+        };
+
+        let gray: Vec<u16> = samples.iter().step_by(channels).copied().collect();
+        check_size(height, width, gray.len(), "PNG")?;
+        let mut world = Self::from_gray(height, width, gray, max_value)?;
+        if channels == 2 {
+            let alpha: Vec<bool> = samples.iter().skip(1).step_by(2).map(|&a| a == 0).collect();
+            world.obstacles = Array2::from_shape_vec((height, width), alpha)?;
+        }
+        Ok(world)
+    }
+
+    /// Saves the world as 16 bit grayscale PNG. If the world has obstacles, an alpha channel is added in which the
+    /// obstacles are fully transparent
+    fn to_png(&self) -> Result<Vec<u8>> {
+        let has_obstacles = self.obstacles.iter().any(|&blocked| blocked);
+
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, self.width as u32, self.height as u32);
+        encoder.set_color(match has_obstacles {
+            true => png::ColorType::GrayscaleAlpha,
+            false => png::ColorType::Grayscale,
+        });
+        encoder.set_depth(png::BitDepth::Sixteen);
+
+        let data: Vec<u8> = self
+            .to_gray(u16::MAX)
+            .iter()
+            .zip(self.obstacles.iter())
+            .flat_map(|(&v, &blocked)| match has_obstacles {
+                true => vec![v, if blocked { 0 } else { u16::MAX }],
+                false => vec![v],
+            })
+            .flat_map(|v| v.to_be_bytes())
+            .collect();
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+
+        Ok(bytes)
+    }
+
+    fn from_csv(text: &str) -> Result<World> {
+        // Obstacles are read as None
+        let rows: Vec<Vec<Option<i64>>> = text
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                line.split(',')
+                    .map(|v| match v.trim() {
+                        Self::OBSTACLE => Ok(None),
+                        v => v.parse().map(Some),
+                    })
+                    .collect()
+            })
+            .collect::<Result<_, _>>()?;
+
+        let height = rows.len();
+        let width = rows.first().map_or(0, |row| row.len());
+        if let Some(y) = rows.iter().position(|row| row.len() != width) {
+            bail!(
+                "Row {} of CSV has {} values, expected {}",
+                y,
+                rows[y].len(),
+                width
+            );
+        }
+
+        check_size(height, width, rows.iter().map(Vec::len).sum(), "CSV")?;
+
+        // The bounds of the world are widened to fit any values outside of the default bounds
+        let cells = Array2::from_shape_vec((height, width), rows.concat())?;
+        let values = cells.iter().flatten();
+        let lower_bound = values.clone().copied().fold(Self::LOWER_BOUND, i64::min);
+        let upper_bound = values.copied().fold(Self::UPPER_BOUND, i64::max);
+
+        let mut world = World::with_bounds(height, width, lower_bound, upper_bound);
+        world.information = cells.mapv(|cell| cell.unwrap_or(0));
+        world.obstacles = cells.mapv(|cell| cell.is_none());
+        Ok(world)
+    }
+
+    fn to_csv(&self) -> String {
+        self.information
+            .rows()
+            .into_iter()
+            .zip(self.obstacles.rows())
+            .map(|(row, blocked)| {
+                let values: Vec<String> = row
+                    .iter()
+                    .zip(blocked.iter())
+                    .map(|(v, &blocked)| match blocked {
+                        true => Self::OBSTACLE.to_string(),
+                        false => v.to_string(),
+                    })
+                    .collect();
+                values.join(",") + "\n"
+            })
+            .collect()
+    }
+
+    /// Reads a world in the binary format. The header consists of the magic bytes, a version, the height and width,
+    /// the lower and upper bound, the seed and the number of bytes per value. Values are stored as their offset from
+    /// the lower bound, in as few bytes as the bounds allow. All numbers are little endian
+    fn from_binary(bytes: &[u8]) -> Result<World> {
+        let mut reader = bytes;
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != Self::MAGIC {
+            bail!("Not a world file");
+        }
+        // Version 1 files have no obstacles and version 2 files have no costs, but both can still be read
+        let version = read_u64(&mut reader, 1)? as u8;
+        if !(1..=Self::VERSION).contains(&version) {
+            bail!("Unsupported world file version {}", version);
+        }
+
+        let height = read_u64(&mut reader, 8)? as usize;
+        let width = read_u64(&mut reader, 8)? as usize;
+        let lower_bound = read_u64(&mut reader, 8)? as i64;
+        let upper_bound = read_u64(&mut reader, 8)? as i64;
+        let seed = match read_u64(&mut reader, 1)? {
+            0 => None,
+            _ => Some(read_u64(&mut reader, 8)?),
+        };
+        let n_bytes = read_u64(&mut reader, 1)? as usize;
+        if ![1, 2, 4, 8].contains(&n_bytes) {
+            bail!("Invalid number of bytes per value: {}", n_bytes);
+        }
+
+        // Check the dimensions against the size of the file before allocating, so that a corrupt header is an error
+        let count = height
+            .checked_mul(width)
+            .filter(|count| {
+                count
+                    .checked_mul(n_bytes)
+                    .is_some_and(|size| size <= reader.len())
+            })
+            .ok_or_else(|| {
+                eyre!(
+                    "World of {}x{} does not fit in the world file",
+                    height,
+                    width
+                )
+            })?;
+
+        let mut information = Array2::zeros((height, width));
+        for val in information.iter_mut() {
+            *val = lower_bound.wrapping_add(read_u64(&mut reader, n_bytes)? as i64);
+        }
+
+        // The world takes on the bounds the file was written with
+        let mut world = World::with_bounds(height, width, lower_bound, upper_bound);
+        world.information = information;
+        world.seed = seed;
+
+        // The obstacles are stored as a bitmap, one bit per location, preceded by a flag whether there are any
+        if 2 <= version && read_u64(&mut reader, 1)? != 0 {
+            let mut bitmap = vec![0u8; count.div_ceil(8)];
+            reader
+                .read_exact(&mut bitmap)
+                .map_err(|_| eyre!("Unexpected end of world file"))?;
+            for (i, blocked) in world.obstacles.iter_mut().enumerate() {
+                *blocked = bitmap[i / 8] & (1 << (i % 8)) != 0;
+            }
+        }
+
+        // The costs are stored as 8 bytes per location, preceded by a flag whether the world has a cost layer
+        if 3 <= version && read_u64(&mut reader, 1)? != 0 {
+            if reader.len() < count * 8 {
+                bail!("Unexpected end of world file");
+            }
+            let mut costs = Array2::zeros((height, width));
+            for cost in costs.iter_mut() {
+                *cost = read_u64(&mut reader, 8)? as i64;
+            }
+            world.costs = Some(costs);
+        }
+        Ok(world)
+    }
+
+    fn to_binary(&self) -> Result<Vec<u8>> {
+        if let Some(v) = self
+            .information
+            .iter()
+            .find(|&&v| !(self.lower_bound..=self.upper_bound).contains(&v))
+        {
+            bail!(
+                "Value {} is outside of the bounds [{}, {}] of the world",
+                v,
+                self.lower_bound,
+                self.upper_bound
+            );
+        }
+
+        // Use the smallest number of bytes that can hold every value between the bounds
+        let range = self.upper_bound.wrapping_sub(self.lower_bound) as u64;
+        let n_bytes = [1, 2, 4, 8]
+            .into_iter()
+            .find(|&n| n == 8 || range < 1 << (8 * n))
+            .unwrap();
+
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&(self.height as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.width as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.lower_bound.to_le_bytes());
+        bytes.extend_from_slice(&self.upper_bound.to_le_bytes());
+        match self.seed {
+            Some(seed) => {
+                bytes.push(1);
+                bytes.extend_from_slice(&seed.to_le_bytes());
+            }
+            None => bytes.push(0),
+        }
+        bytes.push(n_bytes as u8);
+        for &v in self.information.iter() {
+            bytes.extend_from_slice(
+                &(v.wrapping_sub(self.lower_bound) as u64).to_le_bytes()[..n_bytes],
+            );
+        }
+
+        if self.obstacles.iter().any(|&blocked| blocked) {
+            bytes.push(1);
+            let mut bitmap = vec![0u8; (self.height * self.width).div_ceil(8)];
+            for (i, _) in self.obstacles.iter().enumerate().filter(|(_, &b)| b) {
+                bitmap[i / 8] |= 1 << (i % 8);
+            }
+            bytes.extend_from_slice(&bitmap);
+        } else {
+            bytes.push(0);
+        }
+
+        match &self.costs {
+            Some(costs) => {
+                bytes.push(1);
+                for cost in costs.iter() {
+                    bytes.extend_from_slice(&cost.to_le_bytes());
+                }
+            }
+            None => bytes.push(0),
+        }
+
+        Ok(bytes)
+    }
+}
+
+/// Reads a little endian unsigned integer of n_bytes bytes
+/// Checks that a world of height by width locations holds the number of values found in a file, so that the dimensions
+/// in the header of a corrupt file are an error rather than an overflow
+fn check_size(height: usize, width: usize, n_values: usize, format: &str) -> Result<()> {
+    match height.checked_mul(width) {
+        Some(count) if count == n_values => Ok(()),
+        _ => bail!(
+            "Expected {}x{} values in {}, found {}",
+            height,
+            width,
+            format,
+            n_values
+        ),
+    }
+}
+
+fn read_u64(reader: &mut impl Read, n_bytes: usize) -> Result<u64> {
+    let mut buffer = [0u8; 8];
+    reader
+        .read_exact(&mut buffer[..n_bytes])
+        .map_err(|_| eyre!("Unexpected end of world file"))?;
+    Ok(u64::from_le_bytes(buffer))
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, SeedableRng};
+
+    use super::*;
+    use crate::world::generator::NoiseConfig;
+
+    /// A random world with obstacles and costs, generated from the seed
+    fn world_with_layers(seed: u64) -> World {
+        let mut world = World::random(37, 53, 0.3, seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        world.fill_obstacles(0.2, &mut rng);
+        world.fill_costs(5, &NoiseConfig::default(), &mut rng);
+        world
+    }
+
+    #[test]
+    fn binary_round_trip_keeps_values_obstacles_costs_and_seed() {
+        let world = world_with_layers(3);
+        let loaded = World::from_binary(&world.to_binary().unwrap()).unwrap();
+
+        assert_eq!(loaded.get_information(), world.get_information());
+        assert_eq!(loaded.get_obstacles(), world.get_obstacles());
+        assert_eq!(loaded.get_costs(), world.get_costs());
+        assert_eq!(loaded.get_bounds(), world.get_bounds());
+        assert_eq!(loaded.get_seed(), Some(3));
+    }
+
+    #[test]
+    fn csv_round_trip_keeps_values_and_obstacles() {
+        let world = world_with_layers(5);
+        let loaded = World::from_csv(&world.to_csv()).unwrap();
+
+        assert_eq!(loaded.get_obstacles(), world.get_obstacles());
+        // The values of obstacles are not written
+        for ((index, &value), &blocked) in world
+            .get_information()
+            .indexed_iter()
+            .zip(world.get_obstacles().iter())
+        {
+            if !blocked {
+                assert_eq!(loaded.get_information()[index], value, "at {:?}", index);
+            }
+        }
+    }
+
+    #[test]
+    fn png_round_trip_keeps_values_and_obstacles() {
+        let world = world_with_layers(7);
+        let loaded = World::from_png(&world.to_png().unwrap()).unwrap();
+
+        assert_eq!(loaded.get_information(), world.get_information());
+        assert_eq!(loaded.get_obstacles(), world.get_obstacles());
+    }
+
+    #[test]
+    fn pgm_round_trip_keeps_values() {
+        let world = World::random(23, 31, 0.2, 11);
+        let loaded = World::from_pgm(&world.to_pgm()).unwrap();
+        assert_eq!(loaded.get_information(), world.get_information());
+    }
+
+    #[test]
+    fn dimensions_that_do_not_match_the_values_are_rejected() {
+        let huge = "4294967296 4294967296";
+        for header in [
+            format!("P2 {} 100\n1 2 3", huge),
+            format!("P5 {} 255\n", huge),
+        ] {
+            assert!(World::from_pgm(header.as_bytes()).is_err(), "{}", header);
+        }
+        assert!(World::from_pgm(b"P2 2 2 100\n1 2 3").is_err());
+    }
+
+    #[test]
+    fn save_and_load_pick_the_format_from_the_extension() {
+        let world = world_with_layers(9);
+        let path = std::env::temp_dir().join(format!("avlr-{}.world", std::process::id()));
+        world.save(&path).unwrap();
+        let loaded = World::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        let loaded = loaded.unwrap();
+        assert_eq!(loaded.get_information(), world.get_information());
+        assert_eq!(loaded.get_seed(), Some(9));
+    }
+}
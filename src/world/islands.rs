@@ -0,0 +1,241 @@
+use std::{cmp::Ordering, collections::VecDeque};
+
+use ndarray::Array2;
+
+use super::{
+    cell::{CellSum, CellValue},
+    Landscape, Topology, World,
+};
+
+/// A connected group of valuable locations. Locations are connected to all 8 of their neighbours, regardless of the
+/// move model of a problem
+#[derive(Clone, Debug, PartialEq)]
+pub struct Island<S = i64> {
+    pub label: usize,
+    /// Total value of all locations on the island
+    pub value: S,
+    /// Number of locations on the island
+    pub area: usize,
+    /// Mean location of the island, as (y, x). On a toroidal world, the mean is taken over the island as it crosses the
+    /// edges, and then wrapped into the world
+    pub centroid: (f64, f64),
+    /// Smallest block containing the island, as rows y0..y1 and columns x0..x1. On a toroidal world, the block may
+    /// extend past an edge, and wraps around it like any block does. An island that wraps around the entire world
+    /// spans it from edge to edge
+    pub bounding_box: (i64, i64, i64, i64),
+    /// Locations of the island that border a location outside of it
+    pub coast: Vec<(i64, i64)>,
+}
+
+/// The islands of a world, along with a map that labels every location with the island it belongs to
+#[derive(Clone, Debug)]
+pub struct Islands<S = i64> {
+    labels: Array2<Option<usize>>,
+    topology: Topology,
+    islands: Vec<Island<S>>,
+}
+
+impl<S: CellSum> Islands<S> {
+    /// Returns the islands, ordered by label
+    pub fn islands(&self) -> &[Island<S>] {
+        &self.islands
+    }
+
+    /// Returns the islands ordered from the most to the least valuable
+    pub fn by_value(&self) -> Vec<&Island<S>> {
+        let mut islands: Vec<&Island<S>> = self.islands.iter().collect();
+        islands.sort_by(|a, b| b.value.partial_cmp(&a.value).unwrap_or(Ordering::Equal));
+        islands
+    }
+
+    /// Returns the island closest to the given island, and the minimum number of steps between them, or None if there
+    /// is no other island. Steps go to any of the 8 neighbours, across obstacles and around the edges of a toroidal
+    /// world
+    pub fn closest(&self, label: usize) -> Option<(usize, i64)> {
+        let (height, width) = self.labels.dim();
+        let mut distances: Array2<Option<i64>> = Array2::from_elem((height, width), None);
+        let mut queue = VecDeque::new();
+        for &(y, x) in self.islands[label].coast.iter() {
+            distances[[y as usize, x as usize]] = Some(0);
+            queue.push_back((y, x));
+        }
+
+        // Breadth first search from the coast, until the first location of another island is found
+        while let Some((y, x)) = queue.pop_front() {
+            let distance = distances[[y as usize, x as usize]]?;
+            match self.labels[[y as usize, x as usize]] {
+                Some(other) if other != label => return Some((other, distance)),
+                _ => {}
+            }
+            for dy in -1..2 {
+                for dx in -1..2 {
+                    let Some((ny, nx)) = self.wrap(y + dy, x + dx) else {
+                        continue;
+                    };
+                    if distances[[ny as usize, nx as usize]].is_none() {
+                        distances[[ny as usize, nx as usize]] = Some(distance + 1);
+                        queue.push_back((ny, nx));
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the location within the labels, wrapped around the edges on a toroidal world, or None if it lies
+    /// outside of a bounded world
+    fn wrap(&self, y: i64, x: i64) -> Option<(i64, i64)> {
+        let (height, width) = self.labels.dim();
+        let (height, width) = (height as i64, width as i64);
+        match self.topology {
+            Topology::Toroidal => Some((y.rem_euclid(height), x.rem_euclid(width))),
+            Topology::Bounded => {
+                ((0..height).contains(&y) && (0..width).contains(&x)).then_some((y, x))
+            }
+        }
+    }
+}
+
+impl<T: CellValue> World<T> {
+    /// Finds the islands of the world. A location is part of an island if its value is above the threshold and it is
+    /// not blocked. On a toroidal world, islands continue across the edges
+    pub fn islands(&self, threshold: T) -> Islands<T::Sum> {
+        let is_land = |y: i64, x: i64| {
+            self.is_passable(y, x) && threshold < self.information[[y as usize, x as usize]]
+        };
+
+        let mut labels: Array2<Option<usize>> = Array2::from_elem((self.height, self.width), None);
+        let mut islands = Vec::new();
+        let mut queue = VecDeque::new();
+
+        for ((start_y, start_x), _) in self.information.indexed_iter() {
+            let (start_y, start_x) = (start_y as i64, start_x as i64);
+            if labels[[start_y as usize, start_x as usize]].is_some() || !is_land(start_y, start_x)
+            {
+                continue;
+            }
+
+            // Flood fill the island
+            let label = islands.len();
+            let mut island = Island {
+                label,
+                value: T::Sum::default(),
+                area: 0,
+                centroid: (0.0, 0.0),
+                bounding_box: (start_y, start_y + 1, start_x, start_x + 1),
+                coast: Vec::new(),
+            };
+            labels[[start_y as usize, start_x as usize]] = Some(label);
+            // The queue holds unwrapped locations, so that an island crossing an edge of a toroidal world keeps its
+            // shape for its centroid and bounding box
+            queue.push_back((start_y, start_x));
+
+            while let Some((y, x)) = queue.pop_front() {
+                let (wrapped_y, wrapped_x) = self.wrap(y, x);
+                island.value += self.information[[wrapped_y as usize, wrapped_x as usize]].into();
+                island.area += 1;
+                island.centroid.0 += y as f64;
+                island.centroid.1 += x as f64;
+                let (y0, y1, x0, x1) = island.bounding_box;
+                island.bounding_box = (y0.min(y), y1.max(y + 1), x0.min(x), x1.max(x + 1));
+
+                let mut on_coast = false;
+                for dy in -1..2 {
+                    for dx in -1..2 {
+                        let (ny, nx) = self.wrap(y + dy, x + dx);
+                        if !is_land(ny, nx) {
+                            on_coast = true;
+                        } else if labels[[ny as usize, nx as usize]].is_none() {
+                            labels[[ny as usize, nx as usize]] = Some(label);
+                            queue.push_back((y + dy, x + dx));
+                        }
+                    }
+                }
+                if on_coast {
+                    island.coast.push((wrapped_y, wrapped_x));
+                }
+            }
+
+            island.centroid.0 /= island.area as f64;
+            island.centroid.1 /= island.area as f64;
+            if self.topology == Topology::Toroidal {
+                let (height, width) = (self.height as i64, self.width as i64);
+                island.centroid.0 = island.centroid.0.rem_euclid(height as f64);
+                island.centroid.1 = island.centroid.1.rem_euclid(width as f64);
+                let (y0, y1, x0, x1) = island.bounding_box;
+                let (y0, y1) = if height <= y1 - y0 {
+                    (0, height)
+                } else {
+                    (y0, y1)
+                };
+                let (x0, x1) = if width <= x1 - x0 {
+                    (0, width)
+                } else {
+                    (x0, x1)
+                };
+                island.bounding_box = (y0, y1, x0, x1);
+            }
+            islands.push(island);
+        }
+
+        Islands {
+            labels,
+            topology: self.topology,
+            islands,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn world(topology: Topology, land: &[(i64, i64)]) -> World {
+        let mut world = World::new(10, 10);
+        world.set_topology(topology);
+        for &(y, x) in land {
+            world.set(y, x, 10);
+        }
+        world
+    }
+
+    #[test]
+    fn an_island_in_the_corners_wraps_around_a_toroidal_world() {
+        let corners = [(0, 0), (0, 9), (9, 0), (9, 9)];
+        let islands = world(Topology::Toroidal, &corners).islands(0);
+        assert_eq!(islands.islands().len(), 1);
+        let island = &islands.islands()[0];
+        assert_eq!((island.value, island.area), (40, 4));
+        assert_eq!(island.centroid, (9.5, 9.5));
+        assert_eq!(island.bounding_box, (-1, 1, -1, 1));
+        assert_eq!(islands.closest(0), None);
+
+        let islands = world(Topology::Bounded, &corners).islands(0);
+        assert_eq!(islands.islands().len(), 4);
+        assert_eq!(islands.islands()[0].bounding_box, (0, 1, 0, 1));
+    }
+
+    #[test]
+    fn an_island_around_the_entire_world_spans_it() {
+        let row: Vec<(i64, i64)> = (0..10).map(|x| (3, x)).collect();
+        let islands = world(Topology::Toroidal, &row).islands(0);
+        assert_eq!(islands.islands().len(), 1);
+        assert_eq!(islands.islands()[0].bounding_box, (3, 4, 0, 10));
+        assert_eq!(islands.islands()[0].centroid.0, 3.0);
+    }
+
+    #[test]
+    fn the_closest_island_may_lie_across_an_edge() {
+        let land = [(5, 0), (5, 8), (2, 4), (2, 5)];
+        let islands = world(Topology::Toroidal, &land).islands(0);
+        // Labelled in the order they are found, row by row
+        assert_eq!(islands.islands().len(), 3);
+        assert_eq!(islands.closest(1), Some((2, 2)));
+        assert_eq!(islands.closest(2), Some((1, 2)));
+        assert_eq!(islands.by_value()[0].area, 2);
+
+        let islands = world(Topology::Bounded, &land).islands(0);
+        assert_eq!(islands.closest(1), Some((0, 4)));
+        assert_eq!(islands.closest(2), Some((0, 3)));
+    }
+}
@@ -0,0 +1,233 @@
+use std::{borrow::Cow, collections::HashMap, fs, path::Path};
+
+use color_eyre::eyre::{bail, eyre, Result};
+
+use super::cell::CellValue;
+
+/// A single change to a location of the world, made by an algorithm at a certain timestep
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry<T = i64> {
+    pub t: u64,
+    pub y: i64,
+    pub x: i64,
+    /// Value of the location before the change
+    pub before: T,
+    /// Value of the location after the change
+    pub after: T,
+    /// Name of the algorithm. Borrowed while recording, so that recording a visit does not allocate
+    pub algorithm: Cow<'static, str>,
+}
+
+/// The difference of a single location between two timesteps
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LocationDiff<T = i64> {
+    pub y: i64,
+    pub x: i64,
+    pub before: T,
+    pub after: T,
+}
+
+/// Not unlike git, the journal tracks the incremental differences of a world. It is an append-only list of changes,
+/// each recording which algorithm changed which location at what timestep, and what the value was before and after.
+/// Given the current state of a world, the journal can be used to reconstruct the state at any earlier timestep
+#[derive(Clone, Debug)]
+pub struct Journal<T = i64> {
+    entries: Vec<JournalEntry<T>>,
+    /// For every location, the indices of the entries that changed it
+    index: HashMap<(i64, i64), Vec<usize>>,
+}
+
+// Not derived, since that would require T: Default
+impl<T> Default for Journal<T> {
+    fn default() -> Self {
+        Journal {
+            entries: Vec::new(),
+            index: HashMap::new(),
+        }
+    }
+}
+
+impl<T: CellValue> Journal<T> {
+    const HEADER: &'static str = "t,y,x,before,after,algorithm";
+
+    pub fn record(&mut self, entry: JournalEntry<T>) {
+        self.index
+            .entry((entry.y, entry.x))
+            .or_default()
+            .push(self.entries.len());
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[JournalEntry<T>] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Returns all entries of a single location, in the order they were recorded
+    pub fn entries_at(&self, y: i64, x: i64) -> impl Iterator<Item = &JournalEntry<T>> {
+        self.index
+            .get(&(y, x))
+            .into_iter()
+            .flatten()
+            .map(|&i| &self.entries[i])
+    }
+
+    /// Returns the last timestep at or before t at which the location was changed
+    pub fn last_change(&self, y: i64, x: i64, t: u64) -> Option<u64> {
+        self.entries_at(y, x)
+            .map(|entry| entry.t)
+            .filter(|&t_entry| t_entry <= t)
+            .max()
+    }
+
+    /// Returns the locations that differ between timestep t0 and timestep t1, where t0 < t1. For every location, the
+    /// value before the first change after t0 and the value after the last change at or before t1 are given
+    pub fn diff(&self, t0: u64, t1: u64) -> Vec<LocationDiff<T>> {
+        let mut changes: Vec<&JournalEntry<T>> = self
+            .entries
+            .iter()
+            .filter(|entry| t0 < entry.t && entry.t <= t1)
+            .collect();
+        // Stable sort, so that changes within the same timestep keep the order in which they were recorded
+        changes.sort_by_key(|entry| entry.t);
+
+        let mut diffs: Vec<LocationDiff<T>> = Vec::new();
+        let mut positions: HashMap<(i64, i64), usize> = HashMap::new();
+        for entry in changes {
+            match positions.get(&(entry.y, entry.x)) {
+                Some(&i) => diffs[i].after = entry.after,
+                None => {
+                    positions.insert((entry.y, entry.x), diffs.len());
+                    diffs.push(LocationDiff {
+                        y: entry.y,
+                        x: entry.x,
+                        before: entry.before,
+                        after: entry.after,
+                    });
+                }
+            }
+        }
+
+        diffs.retain(|diff| diff.before != diff.after);
+        diffs
+    }
+
+    /// Saves the journal as CSV, one entry per line
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut text = String::from(Self::HEADER) + "\n";
+        for entry in &self.entries {
+            text += &format!(
+                "{},{},{},{},{},{}\n",
+                entry.t, entry.y, entry.x, entry.before, entry.after, entry.algorithm
+            );
+        }
+        let path = path.as_ref();
+        fs::write(path, text).map_err(|e| eyre!("Could not write {}: {}", path.display(), e))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Journal<T>> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .map_err(|e| eyre!("Could not read {}: {}", path.display(), e))?;
+
+        let mut lines = text.lines();
+        if lines.next() != Some(Self::HEADER) {
+            bail!("{} is not a journal file", path.display());
+        }
+
+        let mut journal = Journal::default();
+        for line in lines.filter(|line| !line.is_empty()) {
+            // The algorithm comes last, so that its name may contain commas
+            let fields: Vec<&str> = line.splitn(6, ',').collect();
+            if fields.len() != 6 {
+                bail!("Invalid journal entry: {}", line);
+            }
+            journal.record(JournalEntry {
+                t: fields[0].parse()?,
+                y: fields[1].parse()?,
+                x: fields[2].parse()?,
+                before: fields[3].parse()?,
+                after: fields[4].parse()?,
+                algorithm: Cow::Owned(fields[5].to_string()),
+            });
+        }
+        Ok(journal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(t: u64, (y, x): (i64, i64), before: i64, after: i64) -> JournalEntry {
+        JournalEntry {
+            t,
+            y,
+            x,
+            before,
+            after,
+            algorithm: Cow::Borrowed("test"),
+        }
+    }
+
+    /// A location visited twice, a location that is changed and changed back, and a location visited once
+    fn journal() -> Journal {
+        let mut journal = Journal::default();
+        journal.record(entry(1, (0, 0), 10, 4));
+        journal.record(entry(2, (5, 5), 7, 3));
+        journal.record(entry(3, (0, 0), 4, 0));
+        journal.record(entry(4, (5, 5), 3, 7));
+        journal.record(entry(4, (2, 9), 8, 0));
+        journal
+    }
+
+    #[test]
+    fn diff_spans_the_changes_between_two_timesteps() {
+        let journal = journal();
+        let diff = |y, x, before, after| LocationDiff {
+            y,
+            x,
+            before,
+            after,
+        };
+
+        assert_eq!(
+            journal.diff(0, 4),
+            vec![diff(0, 0, 10, 0), diff(2, 9, 8, 0)]
+        );
+        assert_eq!(journal.diff(1, 2), vec![diff(5, 5, 7, 3)]);
+        assert_eq!(journal.diff(2, 3), vec![diff(0, 0, 4, 0)]);
+        assert_eq!(journal.diff(4, 10), vec![]);
+    }
+
+    #[test]
+    fn last_change_is_at_or_before_the_timestep() {
+        let journal = journal();
+        assert_eq!(journal.last_change(0, 0, 0), None);
+        assert_eq!(journal.last_change(0, 0, 2), Some(1));
+        assert_eq!(journal.last_change(0, 0, 3), Some(3));
+        assert_eq!(journal.last_change(1, 1, 10), None);
+    }
+
+    #[test]
+    fn save_and_load_keep_every_entry() {
+        let mut journal = journal();
+        journal.record(JournalEntry {
+            algorithm: Cow::Borrowed("Hill Climber, Guided"),
+            ..entry(5, (-1, 3), 2, 1)
+        });
+        let path = std::env::temp_dir().join(format!("avlr-{}.csv", std::process::id()));
+        journal.save(&path).unwrap();
+        let loaded: Result<Journal> = Journal::load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap().entries(), journal.entries());
+    }
+}
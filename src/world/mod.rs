@@ -0,0 +1,792 @@
+use cell::CellValue;
+use color_eyre::eyre::{bail, eyre, Result};
+use generator::NoiseConfig;
+use journal::{Journal, JournalEntry, LocationDiff};
+use ndarray::{parallel::prelude::*, Array1, Array2, ArrayView2, Axis, Zip};
+use pyramid::Pyramid;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use statrs::{
+    distribution::{ContinuousCDF, Normal},
+    statistics::Statistics,
+};
+use std::sync::OnceLock;
+use summed_area::SummedAreaTable;
+
+pub mod attempt;
+pub mod cell;
+pub mod generator;
+pub mod harvest;
+pub mod io;
+pub mod islands;
+pub mod journal;
+pub mod overlay;
+pub mod pyramid;
+pub mod regenerating;
+pub mod scenarios;
+pub mod stats;
+pub mod summed_area;
+pub mod tile_index;
+pub mod tiled;
+
+/// This trait describes a world as seen by the algorithms. A world is a grid of values that can be looked at, and
+/// visited. Visiting a location collects its value and empties it. Implementations are free to decide how the
+/// values are stored, and how large the world is
+pub trait Landscape {
+    type Value: CellValue;
+
+    fn at(&self, y: i64, x: i64) -> Option<Self::Value>;
+
+    fn set(&mut self, y: i64, x: i64, value: Self::Value);
+
+    fn in_world(&self, y: i64, x: i64) -> bool;
+
+    /// Returns whether a location can be moved to. By default, every location within the world can be moved to
+    fn is_passable(&self, y: i64, x: i64) -> bool {
+        self.in_world(y, x)
+    }
+
+    fn visit(&mut self, y: i64, x: i64) -> Option<Self::Value> {
+        let value = self.at(y, x);
+        if value.is_some() {
+            self.set(y, x, Self::Value::default());
+        }
+        value
+    }
+}
+
+/// How a world behaves at its edges
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// Locations beyond the edges are outside of the world
+    #[default]
+    Bounded,
+    /// The world wraps around at its edges, so that every coordinate lies within it. Coordinates are taken modulo the
+    /// size of the world
+    Toroidal,
+}
+
+/// A world is represented by a 2D array of values, of height by width locations. Generated worlds remember the seed they were generated
+/// with, so that they can be regenerated bit-for-bit. The changes of a journal can be applied to a world, after which it can be turned back to any timestep.
+/// Besides values, a world has a layer of obstacles. Blocked locations, such as walls and no-fly zones, can not be moved to.
+/// Optionally, a world has a layer of costs, being the cost of moving onto a location, such as terrain difficulty.
+/// The type of the values and the bounds they lie within can be chosen per world. A world can wrap around at its edges
+#[derive(Clone)]
+pub struct World<T: CellValue = i64> {
+    height: usize,
+    width: usize,
+    information: Array2<T>,
+    lower_bound: T,
+    upper_bound: T,
+    obstacles: Array2<bool>,
+    /// None if moving onto any location is free
+    costs: Option<Array2<i64>>,
+    topology: Topology,
+    seed: Option<u64>,
+    /// Sparsity the world was filled with, if any
+    sparsity: Option<f64>,
+    journal: Journal<T>,
+    /// Built on the first call to `block_sum`, and kept up to date by `set` from then on
+    summed_area: OnceLock<SummedAreaTable<T::Sum>>,
+    /// Built through `build_pyramid`, and kept up to date by `set` from then on
+    pyramids: Vec<Pyramid<T::Sum>>,
+}
+
+#[allow(dead_code)]
+impl<T: CellValue> World<T> {
+    /// Default bounds of new worlds
+    pub const LOWER_BOUND: T = T::LOWER;
+    pub const UPPER_BOUND: T = T::UPPER;
+
+    pub fn new(height: usize, width: usize) -> World<T> {
+        World::with_bounds(height, width, Self::LOWER_BOUND, Self::UPPER_BOUND)
+    }
+
+    /// Creates an empty world whose values lie between the given bounds
+    pub fn with_bounds(height: usize, width: usize, lower_bound: T, upper_bound: T) -> World<T> {
+        let information = Array2::from_elem((height, width), T::default());
+        let obstacles = Array2::from_elem((height, width), false);
+        World {
+            height,
+            width,
+            information,
+            lower_bound,
+            upper_bound,
+            obstacles,
+            costs: None,
+            topology: Topology::default(),
+            seed: None,
+            sparsity: None,
+            journal: Journal::default(),
+            summed_area: OnceLock::new(),
+            pyramids: Vec::new(),
+        }
+    }
+
+    /// Creates a new world with random values, generated from the given seed
+    pub fn random(height: usize, width: usize, sparsity: f64, seed: u64) -> World<T> {
+        let mut world = World::new(height, width);
+        world.fill_random(sparsity, &mut StdRng::seed_from_u64(seed));
+        world.seed = Some(seed);
+        world
+    }
+
+    /// Creates a new world with perlin noise, generated from the given seed. Fails if the sparsity can not be applied,
+    /// see `fill_noise`
+    pub fn perlin(height: usize, width: usize, sparsity: f64, seed: u64) -> Result<World<T>> {
+        World::noise(height, width, sparsity, &NoiseConfig::default(), seed)
+    }
+
+    /// Creates a new world with the configured noise, generated from the given seed. Fails if the sparsity can not be
+    /// applied, see `fill_noise`
+    pub fn noise(
+        height: usize,
+        width: usize,
+        sparsity: f64,
+        config: &NoiseConfig,
+        seed: u64,
+    ) -> Result<World<T>> {
+        let mut world = World::new(height, width);
+        world.fill_noise(sparsity, config, &mut StdRng::seed_from_u64(seed))?;
+        world.seed = Some(seed);
+        Ok(world)
+    }
+
+    /// Fills the world with random values drawn from the given rng
+    /// sparsity: 0.0 - 1.0
+    pub fn fill_random<R: Rng>(&mut self, sparsity: f64, rng: &mut R) {
+        self.sparsity = Some(sparsity);
+        for val in self.information.iter_mut() {
+            if sparsity < rng.random_range(0.0..1.0) {
+                *val = rng.random_range(self.lower_bound..self.upper_bound);
+            }
+        }
+        self.rebuild_aggregates();
+    }
+
+    /// Fills the world with perlin noise. The seed of the noise function is drawn from the given rng
+    pub fn fill_perlin<R: Rng>(&mut self, sparsity: f64, rng: &mut R) -> Result<()> {
+        self.fill_noise(sparsity, &NoiseConfig::default(), rng)
+    }
+
+    /// Fills the world with the configured noise. The seed of the noise function is drawn from the given rng.
+    /// The noise is evaluated and rescaled in parallel, with the same result as on a single thread. Fails if a sparsity
+    /// is given but the noise has no spread, such as in a world of a single location
+    pub fn fill_noise<R: Rng>(
+        &mut self,
+        sparsity: f64,
+        config: &NoiseConfig,
+        rng: &mut R,
+    ) -> Result<()> {
+        self.sparsity = Some(sparsity);
+        let seed = rng.random_range(0..u32::MAX);
+
+        let (lower, upper) = (self.lower_bound.to_f64(), self.upper_bound.to_f64());
+        self.information = sample_noise(config, seed, (self.height, self.width), |noise_val| {
+            T::from_f64(noise_to_value(noise_val, lower, upper))
+        });
+
+        if 0.0 < sparsity {
+            // Shift and rescale all values. The cutoff is quantised like the values themselves
+            let (zscore, max_value) =
+                sparsity_cutoff(self.information.iter().map(|v| v.to_f64()), sparsity, |v| {
+                    T::from_f64(v).to_f64()
+                })?;
+            Zip::from(&mut self.information).par_for_each(|val| {
+                *val = T::from_f64(rescale(val.to_f64(), zscore, max_value, lower, upper));
+            });
+        }
+        self.rebuild_aggregates();
+        Ok(())
+    }
+
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+
+    pub fn get_topology(&self) -> Topology {
+        self.topology
+    }
+
+    pub fn set_topology(&mut self, topology: Topology) {
+        self.topology = topology;
+    }
+
+    /// Maps a location onto the stored locations of the world. On a toroidal world, coordinates are taken modulo the
+    /// size of the world. On a bounded world, locations are returned as is
+    pub fn wrap(&self, y: i64, x: i64) -> (i64, i64) {
+        match self.topology {
+            Topology::Toroidal if 0 < self.height && 0 < self.width => (
+                y.rem_euclid(self.height as i64),
+                x.rem_euclid(self.width as i64),
+            ),
+            _ => (y, x),
+        }
+    }
+
+    /// Returns whether a location lies within the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1.
+    /// On a toroidal world, blocks wrap around the edges like locations do
+    pub fn block_contains(&self, (y0, y1, x0, x1): (i64, i64, i64, i64), y: i64, x: i64) -> bool {
+        match self.topology {
+            Topology::Bounded => (y0..y1).contains(&y) && (x0..x1).contains(&x),
+            Topology::Toroidal => {
+                let (height, width) = (self.height as i64, self.width as i64);
+                (y - y0).rem_euclid(height) < (y1 - y0).min(height)
+                    && (x - x0).rem_euclid(width) < (x1 - x0).min(width)
+            }
+        }
+    }
+
+    /// Returns the index into the layers of the world of a location, or None if it lies outside of the world
+    fn index(&self, y: i64, x: i64) -> Option<[usize; 2]> {
+        let (y, x) = self.wrap(y, x);
+        let in_bounds = 0 <= y && y < self.height as i64 && 0 <= x && x < self.width as i64;
+        in_bounds.then_some([y as usize, x as usize])
+    }
+
+    /// Returns the bounds the values of the world lie within, as (lower, upper)
+    pub fn get_bounds(&self) -> (T, T) {
+        (self.lower_bound, self.upper_bound)
+    }
+
+    /// Converts the world to another type of values, between the given bounds. Values are rescaled from the bounds
+    /// of this world to the new bounds. Obstacles and the seed are kept, the journal is not
+    pub fn convert<U: CellValue>(&self, lower_bound: U, upper_bound: U) -> World<U> {
+        let (lower, upper) = (self.lower_bound.to_f64(), self.upper_bound.to_f64());
+        let (new_lower, new_upper) = (lower_bound.to_f64(), upper_bound.to_f64());
+        let range = (upper - lower).max(f64::EPSILON);
+
+        let mut world = World::with_bounds(self.height, self.width, lower_bound, upper_bound);
+        world.information = self.information.mapv(|v| {
+            let value_norm = (v.to_f64() - lower) / range;
+            U::from_f64(new_lower + value_norm * (new_upper - new_lower))
+        });
+        world.obstacles = self.obstacles.clone();
+        world.costs = self.costs.clone();
+        world.topology = self.topology;
+        world.seed = self.seed;
+        world.sparsity = self.sparsity;
+        world
+    }
+
+    /// Blocks the given fraction of locations at random, drawn from the given rng. Blocked locations are emptied
+    /// fraction: 0.0 - 1.0
+    pub fn fill_obstacles<R: Rng>(&mut self, fraction: f64, rng: &mut R) {
+        Zip::from(&mut self.information)
+            .and(&mut self.obstacles)
+            .for_each(|val, blocked| {
+                if rng.random_range(0.0..1.0) < fraction {
+                    *val = T::default();
+                    *blocked = true;
+                }
+            });
+        self.rebuild_aggregates();
+    }
+
+    pub fn is_blocked(&self, y: i64, x: i64) -> bool {
+        self.index(y, x).is_some_and(|index| self.obstacles[index])
+    }
+
+    pub fn set_blocked(&mut self, y: i64, x: i64, blocked: bool) {
+        if let Some(index) = self.index(y, x) {
+            self.obstacles[index] = blocked;
+        }
+    }
+
+    pub fn get_obstacles(&self) -> ArrayView2<'_, bool> {
+        self.obstacles.view()
+    }
+
+    /// Fills the cost layer with the configured noise, so that costs vary smoothly like terrain. Costs lie between 0
+    /// and max_cost. The seed of the noise function is drawn from the given rng
+    pub fn fill_costs<R: Rng>(&mut self, max_cost: i64, config: &NoiseConfig, rng: &mut R) {
+        let seed = rng.random_range(0..u32::MAX);
+        self.costs = Some(sample_noise(
+            config,
+            seed,
+            (self.height, self.width),
+            |noise_val| {
+                noise_to_value(noise_val, 0.0, max_cost as f64).clamp(0.0, max_cost as f64) as i64
+            },
+        ));
+    }
+
+    /// Returns the cost of moving onto a location. Moving onto a location is free if the world has no cost layer
+    pub fn cost_at(&self, y: i64, x: i64) -> i64 {
+        match (&self.costs, self.index(y, x)) {
+            (Some(costs), Some(index)) => costs[index],
+            _ => 0,
+        }
+    }
+
+    pub fn set_cost(&mut self, y: i64, x: i64, cost: i64) {
+        if let Some(index) = self.index(y, x) {
+            let (height, width) = (self.height, self.width);
+            let costs = self
+                .costs
+                .get_or_insert_with(|| Array2::zeros((height, width)));
+            costs[index] = cost;
+        }
+    }
+
+    pub fn get_costs(&self) -> Option<ArrayView2<'_, i64>> {
+        self.costs.as_ref().map(|costs| costs.view())
+    }
+
+    /// Draws random locations from the given rng until one is found that can be moved to. Returns None if no such
+    /// location is found within a reasonable number of tries
+    pub fn random_passable_location<R: Rng>(&self, rng: &mut R) -> Option<(i64, i64)> {
+        if self.height == 0 || self.width == 0 {
+            return None;
+        }
+        (0..1000)
+            .map(|_| {
+                let x = rng.random_range(0..self.width) as i64;
+                let y = rng.random_range(0..self.height) as i64;
+                (y, x)
+            })
+            .find(|&(y, x)| self.is_passable(y, x))
+    }
+
+    /// Applies the changes of a journal in the order they were recorded, and records them in the journal of the
+    /// world. Fails if a change lies outside of the world, or if the value before it does not match the world, in
+    /// which case the journal was recorded on another world
+    pub fn apply(&mut self, journal: &Journal<T>) -> Result<()> {
+        for entry in journal.entries() {
+            let (y, x) = self.wrap(entry.y, entry.x);
+            match self.at(y, x) {
+                Some(value) if value == entry.before => {}
+                Some(value) => bail!(
+                    "The journal changes ({}, {}) from {} at timestep {}, but the world holds {}",
+                    entry.y,
+                    entry.x,
+                    entry.before,
+                    entry.t,
+                    value
+                ),
+                None => bail!(
+                    "The journal changes ({}, {}) at timestep {}, outside of the world",
+                    entry.y,
+                    entry.x,
+                    entry.t
+                ),
+            }
+            self.set(y, x, entry.after);
+            self.journal.record(JournalEntry {
+                y,
+                x,
+                ..entry.clone()
+            });
+        }
+        Ok(())
+    }
+
+    /// Reconstructs the world as it was at timestep t, by undoing all journal entries made after t
+    pub fn at_timestep(&self, t: u64) -> World<T> {
+        let mut world = self.clone();
+        world.journal = Journal::default();
+
+        for entry in self.journal.entries().iter().rev() {
+            if t < entry.t {
+                world.set(entry.y, entry.x, entry.before);
+            }
+        }
+        for entry in self.journal.entries() {
+            if entry.t <= t {
+                world.journal.record(entry.clone());
+            }
+        }
+        world
+    }
+
+    /// Returns the locations that differ between timestep t0 and timestep t1
+    pub fn diff(&self, t0: u64, t1: u64) -> Vec<LocationDiff<T>> {
+        self.journal.diff(t0, t1)
+    }
+
+    /// Returns the seed the world was generated with, if any
+    pub fn get_seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub fn get_information(&self) -> ArrayView2<'_, T> {
+        self.information.view()
+    }
+
+    /// Returns the sum of the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1. Takes four lookups in
+    /// the summed-area table per part of the block, plus a pass over the visits that have not been folded into it yet,
+    /// of which there are at most sqrt(area) / 8. Blocks are split into parts with `block_parts`
+    pub fn block_sum(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> T::Sum {
+        let summed_area = self
+            .summed_area
+            .get_or_init(|| SummedAreaTable::new(self.information.view()));
+        self.block_parts(y0, y1, x0, x1)
+            .into_iter()
+            .map(|(y0, y1, x0, x1)| summed_area.sum(y0, y1, x0, x1))
+            .sum()
+    }
+
+    /// Splits the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1, into the parts that lie within the
+    /// world. On a bounded world, the block is clamped to the world, so that blocks partially or completely outside of
+    /// it are allowed. On a toroidal world, the block wraps around the edges into at most four parts, which cover every
+    /// location at most once
+    pub fn block_parts(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> Vec<(i64, i64, i64, i64)> {
+        let (height, width) = (self.height as i64, self.width as i64);
+        let (rows, cols) = match self.topology {
+            Topology::Bounded => {
+                let clamp = |a0: i64, a1: i64, length: i64| {
+                    let (a0, a1) = (a0.clamp(0, length), a1.clamp(0, length));
+                    match a0 < a1 {
+                        true => vec![(a0, a1)],
+                        false => Vec::new(),
+                    }
+                };
+                (clamp(y0, y1, height), clamp(x0, x1, width))
+            }
+            Topology::Toroidal => (wrap_range(y0, y1, height), wrap_range(x0, x1, width)),
+        };
+        rows.iter()
+            .flat_map(|&(y0, y1)| cols.iter().map(move |&(x0, x1)| (y0, y1, x0, x1)))
+            .collect()
+    }
+
+    /// Builds a pyramid of the world, summing blocks of factor by factor locations per level. The pyramid is kept up to
+    /// date as locations are visited. Building a pyramid that already exists returns the existing one
+    pub fn build_pyramid(&mut self, factor: usize) -> &Pyramid<T::Sum> {
+        let index = match self
+            .pyramids
+            .iter()
+            .position(|pyramid| pyramid.get_factor() == factor)
+        {
+            Some(index) => index,
+            None => {
+                self.pyramids
+                    .push(Pyramid::new(self.information.view(), factor));
+                self.pyramids.len() - 1
+            }
+        };
+        &self.pyramids[index]
+    }
+
+    /// Returns the pyramid with the given factor, if it has been built
+    pub fn get_pyramid(&self, factor: usize) -> Option<&Pyramid<T::Sum>> {
+        self.pyramids
+            .iter()
+            .find(|pyramid| pyramid.get_factor() == factor)
+    }
+
+    /// Brings the summed-area table and the pyramids in line with the values of the world, after they have been
+    /// changed without going through `set`
+    fn rebuild_aggregates(&mut self) {
+        self.summed_area = OnceLock::new();
+        for pyramid in self.pyramids.iter_mut() {
+            *pyramid = Pyramid::new(self.information.view(), pyramid.get_factor());
+        }
+    }
+}
+
+impl<T: CellValue> Landscape for World<T> {
+    type Value = T;
+
+    fn at(&self, y: i64, x: i64) -> Option<T> {
+        self.index(y, x).map(|index| self.information[index])
+    }
+
+    fn set(&mut self, y: i64, x: i64, value: T) {
+        if let Some(index) = self.index(y, x) {
+            let (y, x) = (index[0] as i64, index[1] as i64);
+            let cell = &mut self.information[index];
+            let delta = T::Sum::from(value) - T::Sum::from(*cell);
+            *cell = value;
+
+            if let Some(summed_area) = self.summed_area.get_mut() {
+                if !summed_area.correct(y, x, delta) {
+                    *summed_area = SummedAreaTable::new(self.information.view());
+                }
+            }
+            for pyramid in self.pyramids.iter_mut() {
+                pyramid.update(y, x, value.into());
+            }
+        }
+    }
+
+    fn in_world(&self, y: i64, x: i64) -> bool {
+        self.index(y, x).is_some()
+    }
+
+    fn is_passable(&self, y: i64, x: i64) -> bool {
+        self.index(y, x).is_some_and(|index| !self.obstacles[index])
+    }
+}
+
+/// Splits the range a0..a1 on an axis of the given length that wraps around into at most two ranges within 0..length.
+/// Ranges longer than the axis cover it once
+fn wrap_range(a0: i64, a1: i64, length: i64) -> Vec<(i64, i64)> {
+    if a1 <= a0 || length == 0 {
+        return Vec::new();
+    }
+    let start = a0.rem_euclid(length);
+    let end = start + (a1 - a0).min(length);
+    if end <= length {
+        vec![(start, end)]
+    } else {
+        vec![(start, length), (0, end - length)]
+    }
+}
+
+/// Evaluates the configured noise at every location of an array of the given shape, and maps it to a value. Rows are
+/// evaluated in parallel. Noise functions are not thread safe, so every thread builds its own from the same seed.
+/// Both axes are scaled by the longest side, so that the noise is not stretched in non-square worlds
+fn sample_noise<V: Copy + Default + Send + Sync>(
+    config: &NoiseConfig,
+    seed: u32,
+    (height, width): (usize, usize),
+    to_value: impl Fn(f64) -> V + Sync,
+) -> Array2<V> {
+    let scale = usize::max(height, width) as f64;
+    let mut values = Array2::from_elem((height, width), V::default());
+    values
+        .axis_iter_mut(Axis(0))
+        .into_par_iter()
+        .enumerate()
+        .for_each_init(
+            || config.build(seed),
+            |noise, (y, mut row)| {
+                for (x, value) in row.iter_mut().enumerate() {
+                    *value = to_value(noise.get([x as f64 / scale, y as f64 / scale]));
+                }
+            },
+        );
+    values
+}
+
+/// Maps a noise value in the range [-1, 1] to a value between the given bounds
+fn noise_to_value(noise_val: f64, lower: f64, upper: f64) -> f64 {
+    lower + (upper - lower) * (noise_val + 1.0) / 2.0
+}
+
+/// Computes the cutoff and the maximum value needed to apply sparsity to the given values. Using the mean and
+/// standard deviation of the values, a cutoff is calculated below which the given fraction of values would fall.
+/// Both are quantised to the type of the values. The mean and standard deviation are computed on a single thread, since
+/// summing in parallel would change their rounding, and with it the generated world. Fails if the values have no
+/// spread, as no normal distribution fits them
+fn sparsity_cutoff(
+    values: impl Iterator<Item = f64>,
+    sparsity: f64,
+    quantise: impl Fn(f64) -> f64,
+) -> Result<(f64, f64)> {
+    // Flatten to f64 for stats
+    let flattened: Array1<f64> = values.collect();
+
+    // Mean & standard deviation (population or sample depends on your use case)
+    let mean = flattened.view().mean();
+    let std = flattened.view().std(0.0); // 0.0 = population, 1.0 = sample
+
+    tracing::info!("Mean: {}", mean);
+    tracing::info!("Std dev: {}", std);
+
+    // Compute z-score cutoff from Normal distribution
+    let normal = Normal::new(mean, std).map_err(|e| {
+        eyre!(
+            "Can not apply a sparsity to values with mean {} and standard deviation {}: {}",
+            mean,
+            std,
+            e
+        )
+    })?;
+    let zscore = quantise(normal.inverse_cdf(sparsity));
+
+    let max = flattened
+        .par_iter()
+        .copied()
+        .reduce(|| f64::NEG_INFINITY, f64::max);
+    let max_value = quantise(max) - zscore;
+    tracing::info!("Zscore: {}", zscore);
+
+    Ok((zscore, max_value))
+}
+
+/// Shifts a value down by the sparsity cutoff, and rescales it to the given bounds
+fn rescale(value: f64, zscore: f64, max_value: f64, lower: f64, upper: f64) -> f64 {
+    let shifted = f64::max(0.0, value - zscore);
+    let scaled = lower + (upper - lower) * shifted / max_value;
+    f64::min(upper, scaled)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::borrow::Cow;
+
+    use super::*;
+
+    /// Visits a location, and records the visit in the journal
+    fn visit(world: &mut World, journal: &mut Journal, y: i64, x: i64, t: u64) {
+        if let Some(before) = world.visit(y, x) {
+            journal.record(JournalEntry {
+                t,
+                y,
+                x,
+                before,
+                after: 0,
+                algorithm: Cow::Borrowed("test"),
+            });
+        }
+    }
+
+    #[test]
+    fn at_timestep_undoes_the_visits_made_after_it() {
+        let original: World = World::random(40, 60, 0.2, 13);
+        let (mut world, mut journal) = (original.clone(), Journal::default());
+        let mut rng = StdRng::seed_from_u64(17);
+        let mut snapshots = vec![world.get_information().to_owned()];
+        for t in 1..=50 {
+            for _ in 0..20 {
+                let (y, x) = (rng.random_range(0..40), rng.random_range(0..60));
+                visit(&mut world, &mut journal, y, x, t);
+            }
+            snapshots.push(world.get_information().to_owned());
+        }
+        let mut world = original;
+        world.apply(&journal).unwrap();
+
+        for (t, snapshot) in snapshots.iter().enumerate() {
+            let past = world.at_timestep(t as u64);
+            assert_eq!(past.get_information(), snapshot.view(), "timestep {}", t);
+            // The journal of the past world ends at the timestep
+            assert!(past.diff(t as u64, u64::MAX).is_empty());
+        }
+    }
+
+    #[test]
+    fn diff_matches_the_snapshots_it_spans() {
+        let original: World = World::random(30, 30, 0.2, 19);
+        let (mut world, mut journal) = (original.clone(), Journal::default());
+        let mut rng = StdRng::seed_from_u64(23);
+        let mut snapshots = vec![world.get_information().to_owned()];
+        for t in 1..=10 {
+            for _ in 0..30 {
+                let (y, x) = (rng.random_range(0..30), rng.random_range(0..30));
+                visit(&mut world, &mut journal, y, x, t);
+            }
+            snapshots.push(world.get_information().to_owned());
+        }
+        let mut world = original;
+        world.apply(&journal).unwrap();
+
+        for (t0, t1) in [(0, 10), (2, 5), (7, 8)] {
+            let (before, after) = (&snapshots[t0], &snapshots[t1]);
+            let mut expected: Vec<(i64, i64)> =
+                Zip::indexed(before)
+                    .and(after)
+                    .fold(Vec::new(), |mut changed, (y, x), b, a| {
+                        if b != a {
+                            changed.push((y as i64, x as i64));
+                        }
+                        changed
+                    });
+            let mut actual: Vec<(i64, i64)> = Vec::new();
+            for diff in world.diff(t0 as u64, t1 as u64) {
+                let index = (diff.y as usize, diff.x as usize);
+                assert_eq!((diff.before, diff.after), (before[index], after[index]));
+                actual.push((diff.y, diff.x));
+            }
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "diff from {} to {}", t0, t1);
+        }
+    }
+
+    #[test]
+    fn a_journal_of_another_world_is_not_applied() {
+        let mut journal = Journal::default();
+        visit(&mut World::random(10, 10, 0.0, 1), &mut journal, 3, 4, 1);
+        let mut world: World = World::random(10, 10, 0.0, 2);
+        let error = world.apply(&journal).unwrap_err().to_string();
+        assert!(error.contains("but the world holds"), "{}", error);
+
+        let mut small: World = World::random(2, 2, 0.0, 1);
+        let error = small.apply(&journal).unwrap_err().to_string();
+        assert!(error.contains("outside of the world"), "{}", error);
+    }
+
+    #[test]
+    fn block_sum_stays_correct_after_visits_and_sets() {
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            let mut world: World = World::random(64, 48, 0.3, 29);
+            world.set_topology(topology);
+            let mut rng = StdRng::seed_from_u64(31);
+            // Enough changes for the summed-area table to be rebuilt several times
+            for i in 0..600 {
+                let (y, x) = (rng.random_range(-10..74), rng.random_range(-10..58));
+                match i % 3 {
+                    0 => world.set(y, x, rng.random_range(0..100)),
+                    _ => {
+                        world.visit(y, x);
+                    }
+                }
+
+                let (y0, x0) = (rng.random_range(-30..70), rng.random_range(-30..55));
+                let (y1, x1) = (y0 + rng.random_range(0..80), x0 + rng.random_range(0..60));
+                let (rows, cols) = match topology {
+                    Topology::Bounded => (y0..y1, x0..x1),
+                    Topology::Toroidal => (y0..y1.min(y0 + 64), x0..x1.min(x0 + 48)),
+                };
+                let expected: i64 = rows
+                    .flat_map(|y| cols.clone().map(move |x| (y, x)))
+                    .filter_map(|(y, x)| world.at(y, x))
+                    .sum();
+                assert_eq!(
+                    world.block_sum(y0, y1, x0, x1),
+                    expected,
+                    "block ({}..{}, {}..{}) on a {:?} world after {} changes",
+                    y0,
+                    y1,
+                    x0,
+                    x1,
+                    topology,
+                    i + 1
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn generation_does_not_depend_on_the_number_of_threads() {
+        let ridged = NoiseConfig {
+            kind: generator::NoiseKind::RidgedMulti,
+            warp: Some(generator::DomainWarp {
+                strength: 0.5,
+                frequency: 2.0,
+            }),
+            ..NoiseConfig::default()
+        };
+        let generate = || {
+            let perlin: World = World::perlin(97, 131, 0.5, 37).unwrap();
+            let ridged: World<f64> = World::noise(64, 80, 0.3, &ridged, 41).unwrap();
+            let mut costs: World = World::new(50, 70);
+            costs.fill_costs(9, &NoiseConfig::default(), &mut StdRng::seed_from_u64(43));
+            (perlin, ridged, costs)
+        };
+
+        let single = rayon::ThreadPoolBuilder::new()
+            .num_threads(1)
+            .build()
+            .unwrap()
+            .install(generate);
+        let multi = rayon::ThreadPoolBuilder::new()
+            .num_threads(4)
+            .build()
+            .unwrap()
+            .install(generate);
+
+        assert_eq!(single.0.get_information(), multi.0.get_information());
+        assert_eq!(single.1.get_information(), multi.1.get_information());
+        assert_eq!(single.2.get_costs(), multi.2.get_costs());
+    }
+}
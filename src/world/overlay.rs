@@ -0,0 +1,220 @@
+use std::{borrow::Cow, collections::HashMap, sync::Arc};
+
+use super::{
+    cell::CellValue,
+    harvest::HarvestModel,
+    journal::{Journal, JournalEntry},
+    tile_index::TileIndex,
+    Landscape, World,
+};
+
+/// A copy-on-write view of a shared world. Changes, such as visits, are stored in a sparse overlay instead of in the
+/// world itself, so that every attempt of an algorithm can start from the same world without copying it. Creating an
+/// overlay is cheap, and its memory usage only grows with the number of locations that are changed.
+/// How much a visit collects is determined by the harvest model of the overlay. Changes are stored by the location as
+/// wrapped by the world, so that on a toroidal world every coordinate of a location shares its changes. They are also
+/// bucketed by tile, so that a block sum only looks at the changes in the tiles the block overlaps
+#[derive(Clone)]
+pub struct VisitOverlay<T: CellValue = i64> {
+    world: Arc<World<T>>,
+    harvest: HarvestModel,
+    /// Current values of the locations that have been changed
+    changes: HashMap<(i64, i64), T>,
+    /// Locations that have been changed, by the tile they lie in
+    tiles: TileIndex,
+    /// Number of times every location has been visited
+    visits: HashMap<(i64, i64), u32>,
+    journal: Journal<T>,
+}
+
+impl<T: CellValue> VisitOverlay<T> {
+    pub fn with_harvest(world: Arc<World<T>>, harvest: HarvestModel) -> VisitOverlay<T> {
+        VisitOverlay {
+            world,
+            harvest,
+            changes: HashMap::new(),
+            tiles: TileIndex::default(),
+            visits: HashMap::new(),
+            journal: Journal::default(),
+        }
+    }
+
+    /// Visits a location on behalf of an algorithm at timestep t, and records the visit in the journal of the overlay
+    pub fn visit_by(&mut self, y: i64, x: i64, t: u64, algorithm: &'static str) -> Option<T> {
+        let (y, x) = self.world.wrap(y, x);
+        let before = self.at(y, x)?;
+        let collected = self.visit(y, x)?;
+        self.journal.record(JournalEntry {
+            t,
+            y,
+            x,
+            before,
+            after: self.at(y, x)?,
+            algorithm: Cow::Borrowed(algorithm),
+        });
+        Some(collected)
+    }
+
+    /// Returns the sum of the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1. The sum of the
+    /// underlying world is corrected for the changes in the overlay, looking only at the tiles the block overlaps
+    pub fn block_sum(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> T::Sum {
+        let mut sum = self.world.block_sum(y0, y1, x0, x1);
+        for part in self.world.block_parts(y0, y1, x0, x1) {
+            for (y, x) in self.tiles.in_block(part) {
+                sum += T::Sum::from(self.changes[&(y, x)])
+                    - T::Sum::from(self.world.at(y, x).unwrap());
+            }
+        }
+        sum
+    }
+
+    /// Returns the summed value of a location in the given level of the pyramid of the world with the given factor,
+    /// corrected for the changes in the overlay within the block it covers. Returns None if the world has no such
+    /// pyramid, or the location lies outside of the level
+    pub fn pyramid_at(&self, factor: usize, level: usize, y: i64, x: i64) -> Option<T::Sum> {
+        let pyramid = self.world.get_pyramid(factor)?;
+        let mut sum = pyramid.at(level, y, x)?;
+        for (y, x) in self.tiles.in_block(pyramid.extent(level, y, x)) {
+            sum += T::Sum::from(self.changes[&(y, x)]) - T::Sum::from(self.world.at(y, x).unwrap());
+        }
+        Some(sum)
+    }
+
+    pub fn get_journal(&self) -> &Journal<T> {
+        &self.journal
+    }
+}
+
+impl<T: CellValue> Landscape for VisitOverlay<T> {
+    type Value = T;
+
+    fn at(&self, y: i64, x: i64) -> Option<T> {
+        let (y, x) = self.world.wrap(y, x);
+        match self.changes.get(&(y, x)) {
+            Some(&value) => Some(value),
+            None => self.world.at(y, x),
+        }
+    }
+
+    fn set(&mut self, y: i64, x: i64, value: T) {
+        if self.world.in_world(y, x) {
+            let (y, x) = self.world.wrap(y, x);
+            if self.changes.insert((y, x), value).is_none() {
+                self.tiles.insert(y, x);
+            }
+        }
+    }
+
+    fn in_world(&self, y: i64, x: i64) -> bool {
+        self.world.in_world(y, x)
+    }
+
+    fn is_passable(&self, y: i64, x: i64) -> bool {
+        self.world.is_passable(y, x)
+    }
+
+    /// Collects part of the value of a location, according to the harvest model
+    fn visit(&mut self, y: i64, x: i64) -> Option<T> {
+        let value = self.at(y, x)?;
+        let visits = self.visits.entry(self.world.wrap(y, x)).or_default();
+        let (collected, remaining) = self.harvest.harvest(value, *visits);
+        *visits += 1;
+        self.set(y, x, remaining);
+        Some(collected)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ndarray::Array2;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+    use crate::world::{pyramid::Pyramid, Topology};
+
+    /// Sums the block location by location, wrapping or skipping locations outside of the world like block_sum does
+    fn brute_force_sum(
+        world: &World,
+        overlay: &VisitOverlay,
+        (y0, y1, x0, x1): (i64, i64, i64, i64),
+    ) -> i64 {
+        let (height, width) = (world.get_height() as i64, world.get_width() as i64);
+        let (y1, x1) = match world.get_topology() {
+            Topology::Bounded => (y1, x1),
+            Topology::Toroidal => (y1.min(y0 + height), x1.min(x0 + width)),
+        };
+        (y0..y1)
+            .flat_map(|y| (x0..x1).map(move |x| (y, x)))
+            .filter_map(|(y, x)| overlay.at(y, x))
+            .sum()
+    }
+
+    #[test]
+    fn block_sum_matches_the_visited_values() {
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            let mut world = World::random(150, 170, 0.3, 7);
+            world.set_topology(topology);
+            let world = Arc::new(world);
+            let mut overlay = VisitOverlay::with_harvest(world.clone(), HarvestModel::Full);
+            let mut rng = StdRng::seed_from_u64(11);
+            for _ in 0..2000 {
+                overlay.visit(rng.random_range(-20..190), rng.random_range(-20..190));
+            }
+
+            for _ in 0..200 {
+                let (y0, x0) = (rng.random_range(-80..200), rng.random_range(-80..200));
+                let (y1, x1) = (y0 + rng.random_range(0..200), x0 + rng.random_range(0..200));
+                assert_eq!(
+                    overlay.block_sum(y0, y1, x0, x1),
+                    brute_force_sum(&world, &overlay, (y0, y1, x0, x1)),
+                    "block ({}..{}, {}..{}) on a {:?} world",
+                    y0,
+                    y1,
+                    x0,
+                    x1,
+                    topology
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pyramid_at_matches_a_pyramid_of_the_visited_values() {
+        let mut world: World = World::random(100, 90, 0.3, 3);
+        for factor in [2, 3] {
+            world.build_pyramid(factor);
+        }
+        let mut overlay = VisitOverlay::with_harvest(Arc::new(world), HarvestModel::Full);
+        let mut rng = StdRng::seed_from_u64(5);
+        for _ in 0..500 {
+            overlay.visit(rng.random_range(0..100), rng.random_range(0..90));
+        }
+
+        let visited =
+            Array2::from_shape_fn((100, 90), |(y, x)| overlay.at(y as i64, x as i64).unwrap());
+        for factor in [2, 3] {
+            let expected: Pyramid<i64> = Pyramid::new(visited.view(), factor);
+            // The top level holds a single location
+            let n_levels = (0..)
+                .find(|&level| expected.at(level, 0, 0).is_none())
+                .unwrap();
+            assert_eq!(expected.at(n_levels - 1, 0, 1), None);
+            for level in 0..n_levels {
+                let size = factor.pow(level as u32);
+                for y in 0..100_usize.div_ceil(size) as i64 + 1 {
+                    for x in 0..90_usize.div_ceil(size) as i64 + 1 {
+                        assert_eq!(
+                            overlay.pyramid_at(factor, level, y, x),
+                            expected.at(level, y, x),
+                            "level {} at ({}, {}) with factor {}",
+                            level,
+                            y,
+                            x,
+                            factor
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use ndarray::{s, Array2, ArrayView2};
+
+use super::cell::CellSum;
+
+/// An image pyramid (mipmap) of a world. Level 0 holds the values of the world itself, and every next level sums
+/// blocks of factor by factor locations of the level below it, until a single location remains. Coarse levels point
+/// towards regions with a lot of value, after which the search is refined level by level down to the full resolution
+#[derive(Clone, Debug)]
+pub struct Pyramid<S: CellSum> {
+    factor: usize,
+    levels: Vec<Array2<S>>,
+}
+
+impl<S: CellSum> Pyramid<S> {
+    pub fn new<T: Copy + Into<S>>(information: ArrayView2<T>, factor: usize) -> Pyramid<S> {
+        assert!(2 <= factor, "The factor of a pyramid must be at least 2");
+
+        let mut levels = vec![information.mapv(|v| v.into())];
+        loop {
+            let below = levels.last().unwrap();
+            let (height, width) = below.dim();
+            if height <= 1 && width <= 1 {
+                break;
+            }
+
+            let mut level = Array2::from_elem(
+                (height.div_ceil(factor), width.div_ceil(factor)),
+                S::default(),
+            );
+            for ((y, x), val) in level.indexed_iter_mut() {
+                let block = below.slice(s![
+                    y * factor..usize::min((y + 1) * factor, height),
+                    x * factor..usize::min((x + 1) * factor, width)
+                ]);
+                *val = block.iter().copied().sum();
+            }
+            levels.push(level);
+        }
+
+        Pyramid { factor, levels }
+    }
+
+    pub fn get_factor(&self) -> usize {
+        self.factor
+    }
+
+    /// Returns the summed value of a location in the given level
+    pub fn at(&self, level: usize, y: i64, x: i64) -> Option<S> {
+        if y < 0 || x < 0 {
+            return None;
+        }
+        self.levels
+            .get(level)?
+            .get((y as usize, x as usize))
+            .copied()
+    }
+
+    /// Returns the block of the world covered by a location in the given level, as rows y0..y1 and columns x0..x1
+    pub fn extent(&self, level: usize, y: i64, x: i64) -> (i64, i64, i64, i64) {
+        let size = self.factor.pow(level as u32) as i64;
+        (y * size, (y + 1) * size, x * size, (x + 1) * size)
+    }
+
+    /// Updates the value of a location of the world, and every level above it
+    pub fn update(&mut self, y: i64, x: i64, value: S) {
+        let (mut y, mut x) = (y as usize, x as usize);
+        let delta = value - self.levels[0][[y, x]];
+        for level in self.levels.iter_mut() {
+            level[[y, x]] += delta;
+            (y, x) = (y / self.factor, x / self.factor);
+        }
+    }
+}
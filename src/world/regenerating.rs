@@ -0,0 +1,320 @@
+use std::{borrow::Cow, sync::Arc};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cell::CellValue,
+    journal::{Journal, JournalEntry},
+    tile_index::TileIndex,
+    Landscape, World,
+};
+
+/// This trait is used to implement the ways in which a visited location regenerates over time. Given the original
+/// value of a location, the number of timesteps that have passed since it was last visited and the bounds of the
+/// world, it returns the current value of that location
+pub trait RegenerationModel {
+    fn regenerate<T: CellValue>(
+        &self,
+        original: T,
+        elapsed: u64,
+        y: i64,
+        x: i64,
+        visited_at: u64,
+        bounds: (T, T),
+    ) -> T;
+}
+
+/// Regenerates the value by a fixed amount every timestep, until the original value has been reached. The rate can
+/// be fractional for worlds of floating point values
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Linear {
+    pub rate: f64,
+}
+
+impl Default for Linear {
+    fn default() -> Self {
+        Linear { rate: 1.0 }
+    }
+}
+
+impl RegenerationModel for Linear {
+    fn regenerate<T: CellValue>(
+        &self,
+        original: T,
+        elapsed: u64,
+        _y: i64,
+        _x: i64,
+        _visited_at: u64,
+        _bounds: (T, T),
+    ) -> T {
+        T::from_f64(f64::min(original.to_f64(), self.rate * elapsed as f64))
+    }
+}
+
+/// Regenerates the value quickly at first, only to slow down when near the original value. The value approaches the
+/// original value exponentially, where the rate determines how quickly
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Logarithmic {
+    pub rate: f64,
+}
+
+impl RegenerationModel for Logarithmic {
+    fn regenerate<T: CellValue>(
+        &self,
+        original: T,
+        elapsed: u64,
+        _y: i64,
+        _x: i64,
+        _visited_at: u64,
+        _bounds: (T, T),
+    ) -> T {
+        let remaining = (-self.rate * elapsed as f64).exp();
+        T::from_f64(original.to_f64() * (1.0 - remaining))
+    }
+}
+
+/// Does not regenerate to the original value, but to a completely new random value once the given number of
+/// timesteps has passed. The new value lies within the bounds of the world, and only depends on the seed, the location
+/// and the time of the visit, so that querying the same location at the same time always gives the same value
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Random {
+    pub delay: u64,
+    pub seed: u64,
+}
+
+impl RegenerationModel for Random {
+    fn regenerate<T: CellValue>(
+        &self,
+        _original: T,
+        elapsed: u64,
+        y: i64,
+        x: i64,
+        visited_at: u64,
+        (lower, upper): (T, T),
+    ) -> T {
+        if elapsed < self.delay {
+            return T::default();
+        }
+        // A world whose bounds hold a single value can not regenerate to anything else
+        if upper <= lower {
+            return lower;
+        }
+
+        // Mix the seed, location and time of the visit into a seed for this specific regeneration
+        let mut hash = self.seed;
+        for v in [y as u64, x as u64, visited_at] {
+            hash = (hash ^ v).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            hash ^= hash >> 32;
+        }
+        StdRng::seed_from_u64(hash).random_range(lower..upper)
+    }
+}
+
+/// The regeneration model of a problem, as given in a problem file
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model", rename_all = "snake_case")]
+pub enum Regeneration {
+    Linear(Linear),
+    Logarithmic(Logarithmic),
+    Random(Random),
+}
+
+impl RegenerationModel for Regeneration {
+    fn regenerate<T: CellValue>(
+        &self,
+        original: T,
+        elapsed: u64,
+        y: i64,
+        x: i64,
+        visited_at: u64,
+        bounds: (T, T),
+    ) -> T {
+        match self {
+            Regeneration::Linear(model) => {
+                model.regenerate(original, elapsed, y, x, visited_at, bounds)
+            }
+            Regeneration::Logarithmic(model) => {
+                model.regenerate(original, elapsed, y, x, visited_at, bounds)
+            }
+            Regeneration::Random(model) => {
+                model.regenerate(original, elapsed, y, x, visited_at, bounds)
+            }
+        }
+    }
+}
+
+/// A world in which visited locations regenerate over time. The original world is never modified. Instead, every visit
+/// is recorded in a journal, and the value of a location at any time is derived from its original value and the last
+/// visit before that time. Through the Landscape trait, the world is looked at and visited at the current time, which
+/// is the time of the latest visit. Visits are recorded by the location as wrapped by the world, so that on a toroidal
+/// world every coordinate of a location shares its visits
+#[derive(Clone)]
+pub struct RegeneratingWorld<M: RegenerationModel, T: CellValue = i64> {
+    original: Arc<World<T>>,
+    model: M,
+    journal: Journal<T>,
+    /// Locations that have been visited, by the tile they lie in
+    tiles: TileIndex,
+    now: u64,
+}
+
+impl<M: RegenerationModel, T: CellValue> RegeneratingWorld<M, T> {
+    pub fn new(original: Arc<World<T>>, model: M) -> RegeneratingWorld<M, T> {
+        RegeneratingWorld {
+            original,
+            model,
+            journal: Journal::default(),
+            tiles: TileIndex::default(),
+            now: 0,
+        }
+    }
+
+    /// Returns the value of a location at time t
+    pub fn at(&self, y: i64, x: i64, t: u64) -> Option<T> {
+        let (y, x) = self.original.wrap(y, x);
+        self.value_since(y, x, t, self.journal.last_change(y, x, t))
+    }
+
+    /// Visits a location at time t, and returns the value that was collected. The visit is recorded in the journal
+    /// without an algorithm
+    pub fn visit(&mut self, y: i64, x: i64, t: u64) -> Option<T> {
+        self.visit_by(y, x, t, "")
+    }
+
+    /// Visits a location at time t on behalf of an algorithm, and returns the value that was collected. The current
+    /// time moves on to t, if it is not already later
+    pub fn visit_by(&mut self, y: i64, x: i64, t: u64, algorithm: &'static str) -> Option<T> {
+        let (y, x) = self.original.wrap(y, x);
+        let value = self.at(y, x, t);
+        self.now = self.now.max(t);
+        if let Some(before) = value {
+            if self.journal.entries_at(y, x).next().is_none() {
+                self.tiles.insert(y, x);
+            }
+            self.journal.record(JournalEntry {
+                t,
+                y,
+                x,
+                before,
+                after: T::default(),
+                algorithm: Cow::Borrowed(algorithm),
+            });
+        }
+        value
+    }
+
+    /// Returns the sum of the block of rows y0..y1 and columns x0..x1 at the current time, exclusive of y1 and x1. The
+    /// sum of the original world is corrected for the visited locations, looking only at the tiles the block overlaps
+    pub fn block_sum(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> T::Sum {
+        let mut sum = self.original.block_sum(y0, y1, x0, x1);
+        for part in self.original.block_parts(y0, y1, x0, x1) {
+            for (y, x) in self.tiles.in_block(part) {
+                sum += T::Sum::from(self.at(y, x, self.now).unwrap())
+                    - T::Sum::from(self.original.at(y, x).unwrap());
+            }
+        }
+        sum
+    }
+
+    pub fn get_journal(&self) -> &Journal<T> {
+        &self.journal
+    }
+
+    /// Returns the value of a location at time t, given the time it was last visited
+    fn value_since(&self, y: i64, x: i64, t: u64, last_visit: Option<u64>) -> Option<T> {
+        let original = self.original.at(y, x)?;
+
+        match last_visit {
+            Some(visited_at) if visited_at == t => Some(T::default()),
+            Some(visited_at) => Some(self.model.regenerate(
+                original,
+                t - visited_at,
+                y,
+                x,
+                visited_at,
+                self.original.get_bounds(),
+            )),
+            None => Some(original),
+        }
+    }
+}
+
+impl<M: RegenerationModel, T: CellValue> Landscape for RegeneratingWorld<M, T> {
+    type Value = T;
+
+    fn at(&self, y: i64, x: i64) -> Option<T> {
+        RegeneratingWorld::at(self, y, x, self.now)
+    }
+
+    /// Sets the original value of a location. The original world is copied first if it is shared
+    fn set(&mut self, y: i64, x: i64, value: T) {
+        Arc::make_mut(&mut self.original).set(y, x, value);
+    }
+
+    fn in_world(&self, y: i64, x: i64) -> bool {
+        self.original.in_world(y, x)
+    }
+
+    fn is_passable(&self, y: i64, x: i64) -> bool {
+        self.original.is_passable(y, x)
+    }
+
+    fn visit(&mut self, y: i64, x: i64) -> Option<T> {
+        RegeneratingWorld::visit(self, y, x, self.now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::world::Topology;
+
+    #[test]
+    fn block_sum_matches_the_regenerated_values() {
+        for topology in [Topology::Bounded, Topology::Toroidal] {
+            let mut original: World = World::random(150, 130, 0.3, 7);
+            original.set_topology(topology);
+            let mut world = RegeneratingWorld::new(Arc::new(original), Linear { rate: 3.0 });
+            let mut rng = StdRng::seed_from_u64(11);
+            for t in 0..1500 {
+                world.visit(rng.random_range(-20..170), rng.random_range(-20..150), t);
+            }
+
+            for _ in 0..100 {
+                let (y0, x0) = (rng.random_range(-80..180), rng.random_range(-80..160));
+                let (y1, x1) = (y0 + rng.random_range(0..150), x0 + rng.random_range(0..130));
+                let (y1, x1) = match topology {
+                    Topology::Bounded => (y1, x1),
+                    Topology::Toroidal => (y1.min(y0 + 150), x1.min(x0 + 130)),
+                };
+                let expected: i64 = (y0..y1)
+                    .flat_map(|y| (x0..x1).map(move |x| (y, x)))
+                    .filter_map(|(y, x)| Landscape::at(&world, y, x))
+                    .sum();
+                assert_eq!(world.block_sum(y0, y1, x0, x1), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn random_regeneration_stays_within_the_bounds_of_the_world() {
+        let model = Random { delay: 2, seed: 3 };
+        let mut original: World<f64> = World::random(10, 10, 0.0, 1);
+        original.set(4, 4, 0.5);
+        let mut world = RegeneratingWorld::new(Arc::new(original), model);
+        world.visit(4, 4, 0);
+        assert_eq!(world.at(4, 4, 1), Some(0.0));
+        let values: Vec<f64> = (2..50).map(|t| world.at(4, 4, t).unwrap()).collect();
+        assert!(values.iter().all(|value| (0.0..1.0).contains(value)));
+        // Fractional values are drawn, rather than the whole values of the default bounds of i64 worlds
+        assert!(values.iter().any(|value| value.fract() != 0.0));
+
+        let mut world =
+            RegeneratingWorld::new(Arc::new(World::with_bounds(10, 10, 200, 300)), model);
+        world.visit(4, 4, 0);
+        assert!((2..50).all(|t| (200..300).contains(&world.at(4, 4, t).unwrap())));
+    }
+}
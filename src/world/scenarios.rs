@@ -0,0 +1,318 @@
+use std::collections::VecDeque;
+
+use ndarray::{Array2, Zip};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use super::World;
+
+/// This impl block contains generators for worlds with a known structure. Unlike the random and noise worlds, it is
+/// obvious where the value in these worlds is, which makes it easy to see whether an algorithm actually finds it. The
+/// worlds hold i64 values, like the worlds of problems
+impl World {
+    /// Creates a world with n gaussian hotspots at random locations. Each hotspot has the given amplitude at its center,
+    /// and its value falls off with the given radius (standard deviation). Overlapping hotspots add up, and values
+    /// are capped at the upper bound. A world without locations has no hotspots
+    pub fn hotspots(
+        height: usize,
+        width: usize,
+        n: usize,
+        amplitude: i64,
+        radius: f64,
+        seed: u64,
+    ) -> World {
+        let mut world = World::new(height, width);
+        world.seed = Some(seed);
+        if height == 0 || width == 0 {
+            return world;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let centers: Vec<(f64, f64)> = (0..n)
+            .map(|_| {
+                (
+                    rng.random_range(0.0..height as f64),
+                    rng.random_range(0.0..width as f64),
+                )
+            })
+            .collect();
+
+        Zip::indexed(&mut world.information).for_each(|(y, x), val| {
+            let value: f64 = centers
+                .iter()
+                .map(|&(cy, cx)| {
+                    let distance_sq = (y as f64 - cy).powi(2) + (x as f64 - cx).powi(2);
+                    amplitude as f64 * (-distance_sq / (2.0 * radius * radius)).exp()
+                })
+                .sum();
+            *val = (value as i64).clamp(Self::LOWER_BOUND, Self::UPPER_BOUND);
+        });
+        world
+    }
+
+    /// Creates a world with a linear gradient from the lower bound to the upper bound. The angle (in radians) gives the
+    /// direction in which the values increase, where 0 is along the x axis and PI/2 along the y axis
+    pub fn gradient(height: usize, width: usize, angle: f64) -> World {
+        let (dy, dx) = angle.sin_cos();
+        let project = |y: f64, x: f64| y * dy + x * dx;
+
+        // The lowest and highest projections are found in the corners of the world
+        let (h, w) = (
+            (height as f64 - 1.0).max(0.0),
+            (width as f64 - 1.0).max(0.0),
+        );
+        let corners = [
+            project(0.0, 0.0),
+            project(0.0, w),
+            project(h, 0.0),
+            project(h, w),
+        ];
+        let min = corners.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let range = (max - min).max(f64::EPSILON);
+
+        let mut world = World::new(height, width);
+        Zip::indexed(&mut world.information).for_each(|(y, x), val| {
+            let value_norm = (project(y as f64, x as f64) - min) / range;
+            *val = Self::LOWER_BOUND
+                + (value_norm * (Self::UPPER_BOUND - Self::LOWER_BOUND) as f64).round() as i64;
+        });
+        world
+    }
+
+    /// Creates an empty world crossed by n narrow corridors of the given value. Each corridor is a straight line of the
+    /// given width between two random locations on the edges of the world. A world without locations has no corridors
+    pub fn corridors(
+        height: usize,
+        width: usize,
+        n: usize,
+        corridor_width: f64,
+        value: i64,
+        seed: u64,
+    ) -> World {
+        let mut world = World::new(height, width);
+        world.seed = Some(seed);
+        if height == 0 || width == 0 {
+            return world;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let (h, w) = (height as f64, width as f64);
+        let random_edge_point = |rng: &mut StdRng| match rng.random_range(0..4) {
+            0 => (0.0, rng.random_range(0.0..w)),
+            1 => (h - 1.0, rng.random_range(0.0..w)),
+            2 => (rng.random_range(0.0..h), 0.0),
+            _ => (rng.random_range(0.0..h), w - 1.0),
+        };
+        let segments: Vec<((f64, f64), (f64, f64))> = (0..n)
+            .map(|_| (random_edge_point(&mut rng), random_edge_point(&mut rng)))
+            .collect();
+
+        Zip::indexed(&mut world.information).for_each(|(y, x), val| {
+            let point = (y as f64, x as f64);
+            if segments
+                .iter()
+                .any(|&(a, b)| distance_to_segment(point, a, b) <= corridor_width / 2.0)
+            {
+                *val = value;
+            }
+        });
+        world
+    }
+
+    /// Creates a maze, carved by a randomized depth-first search starting at the top left. Walls are empty and blocked. The value
+    /// of the passages increases with their distance through the maze from the top left, so that the most valuable
+    /// locations are the ones that are hardest to reach
+    pub fn maze(height: usize, width: usize, seed: u64) -> World {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut passages: Array2<bool> = Array2::from_elem((height, width), false);
+
+        // Maze cells are located at odd coordinates. The locations between them are either walls or passages
+        let (cells_y, cells_x) = ((height.max(1) - 1) / 2, (width.max(1) - 1) / 2);
+        if 0 < cells_y && 0 < cells_x {
+            let mut stack = vec![(0, 0)];
+            passages[[1, 1]] = true;
+            while let Some(&(cy, cx)) = stack.last() {
+                // Unvisited neighbouring cells
+                let mut neighbours: Vec<(usize, usize)> = [(-1, 0), (1, 0), (0, -1), (0, 1)]
+                    .iter()
+                    .map(|&(dy, dx)| (cy as i64 + dy, cx as i64 + dx))
+                    .filter(|&(ny, nx)| {
+                        0 <= ny && ny < cells_y as i64 && 0 <= nx && nx < cells_x as i64
+                    })
+                    .map(|(ny, nx)| (ny as usize, nx as usize))
+                    .filter(|&(ny, nx)| !passages[[2 * ny + 1, 2 * nx + 1]])
+                    .collect();
+                neighbours.shuffle(&mut rng);
+
+                match neighbours.first() {
+                    Some(&(ny, nx)) => {
+                        // Knock down the wall between the two cells
+                        passages[[cy + ny + 1, cx + nx + 1]] = true;
+                        passages[[2 * ny + 1, 2 * nx + 1]] = true;
+                        stack.push((ny, nx));
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+        }
+
+        // Distance of every passage from the start of the maze
+        let mut distances: Array2<Option<usize>> = Array2::from_elem((height, width), None);
+        let mut queue = VecDeque::new();
+        if passages.get((1, 1)) == Some(&true) {
+            distances[[1, 1]] = Some(0);
+            queue.push_back((1, 1));
+        }
+        while let Some((y, x)) = queue.pop_front() {
+            let distance = distances[[y, x]].unwrap();
+            for (ny, nx) in [(y - 1, x), (y + 1, x), (y, x - 1), (y, x + 1)] {
+                if passages[[ny, nx]] && distances[[ny, nx]].is_none() {
+                    distances[[ny, nx]] = Some(distance + 1);
+                    queue.push_back((ny, nx));
+                }
+            }
+        }
+        let max_distance = distances
+            .iter()
+            .flatten()
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(1);
+
+        let mut world = World::new(height, width);
+        world.obstacles = passages.mapv(|passage| !passage);
+        Zip::from(&mut world.information)
+            .and(&distances)
+            .for_each(|val, distance| {
+                if let Some(distance) = distance {
+                    // Passages are never empty, so that they can be told apart from walls
+                    let value_norm = *distance as f64 / max_distance as f64;
+                    *val = Self::LOWER_BOUND
+                        + 1
+                        + (value_norm * (Self::UPPER_BOUND - Self::LOWER_BOUND - 1) as f64) as i64;
+                }
+            });
+        world.seed = Some(seed);
+        world
+    }
+}
+
+/// Returns the distance from a point to the line segment between a and b
+fn distance_to_segment(point: (f64, f64), a: (f64, f64), b: (f64, f64)) -> f64 {
+    let (ab_y, ab_x) = (b.0 - a.0, b.1 - a.1);
+    let (ap_y, ap_x) = (point.0 - a.0, point.1 - a.1);
+    let length_sq = ab_y * ab_y + ab_x * ab_x;
+
+    // Position of the projection of the point onto the segment, clamped to the segment
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        ((ap_y * ab_y + ap_x * ab_x) / length_sq).clamp(0.0, 1.0)
+    };
+    let (closest_y, closest_x) = (a.0 + t * ab_y, a.1 + t * ab_x);
+    ((point.0 - closest_y).powi(2) + (point.1 - closest_x).powi(2)).sqrt()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::world::Landscape;
+
+    #[test]
+    fn generators_accept_worlds_without_locations() {
+        for (height, width) in [(0, 0), (0, 30), (30, 0)] {
+            let worlds = [
+                World::hotspots(height, width, 3, 80, 4.0, 1),
+                World::gradient(height, width, 0.5),
+                World::corridors(height, width, 3, 2.0, 70, 1),
+                World::maze(height, width, 1),
+            ];
+            for world in worlds {
+                assert_eq!((world.get_height(), world.get_width()), (height, width));
+            }
+        }
+    }
+
+    #[test]
+    fn a_hotspot_peaks_at_its_center_and_falls_off_with_distance() {
+        let (amplitude, radius) = (100, 4.0);
+        let world = World::hotspots(60, 80, 1, amplitude, radius, 3);
+
+        // The center is the first location drawn from the seed
+        let mut rng = StdRng::seed_from_u64(3);
+        let (cy, cx): (f64, f64) = (rng.random_range(0.0..60.0), rng.random_range(0.0..80.0));
+        for ((y, x), &value) in world.get_information().indexed_iter() {
+            let distance_sq = (y as f64 - cy).powi(2) + (x as f64 - cx).powi(2);
+            let expected = amplitude as f64 * (-distance_sq / (2.0 * radius * radius)).exp();
+            assert!(
+                (value as f64 - expected).abs() <= 1.0,
+                "{} at ({}, {}), expected {}",
+                value,
+                y,
+                x,
+                expected
+            );
+        }
+
+        // The highest value lies next to the center
+        let ((peak_y, peak_x), _) = world
+            .get_information()
+            .indexed_iter()
+            .max_by_key(|&(_, &value)| value)
+            .unwrap();
+        assert!((peak_y as f64 - cy).abs() <= 1.0 && (peak_x as f64 - cx).abs() <= 1.0);
+    }
+
+    #[test]
+    fn corridors_hold_the_corridor_value() {
+        let world = World::corridors(50, 70, 2, 3.0, 60, 5);
+        let values = world.get_information();
+        assert!(values.iter().all(|&value| value == 0 || value == 60));
+        // A corridor runs between two edges, so it is at least as long as its width
+        assert!(3 <= values.iter().filter(|&&value| value == 60).count());
+    }
+
+    #[test]
+    fn a_gradient_rises_from_the_lower_to_the_upper_bound() {
+        let world = World::gradient(10, 41, 0.0);
+        for y in 0..10 {
+            assert_eq!(world.at(y, 0), Some(World::LOWER_BOUND));
+            assert_eq!(world.at(y, 40), Some(World::UPPER_BOUND));
+            for x in 1..41 {
+                assert!(world.at(y, x - 1) <= world.at(y, x));
+            }
+        }
+    }
+
+    #[test]
+    fn maze_walls_are_obstacles_and_every_passage_is_reachable() {
+        let (height, width) = (21, 31);
+        let world = World::maze(height, width, 7);
+        let obstacles = world.get_obstacles();
+
+        // Walls are empty and blocked, passages hold value. The border is a wall
+        for ((y, x), &value) in world.get_information().indexed_iter() {
+            assert_eq!(obstacles[[y, x]], value == 0, "at ({}, {})", y, x);
+            if y == 0 || x == 0 || y == height - 1 || x == width - 1 {
+                assert!(obstacles[[y, x]], "border at ({}, {})", y, x);
+            }
+        }
+
+        // Every passage can be reached from the start with orthogonal steps
+        let mut reached = Array2::from_elem((height, width), false);
+        let mut queue = VecDeque::from([(1, 1)]);
+        reached[[1, 1]] = true;
+        while let Some((y, x)) = queue.pop_front() {
+            for (ny, nx) in [(y - 1, x), (y + 1, x), (y, x - 1), (y, x + 1)] {
+                if !obstacles[[ny, nx]] && !reached[[ny, nx]] {
+                    reached[[ny, nx]] = true;
+                    queue.push_back((ny, nx));
+                }
+            }
+        }
+        assert_eq!(reached, obstacles.mapv(|blocked| !blocked));
+    }
+}
@@ -0,0 +1,186 @@
+use std::{cmp::Ordering, collections::BinaryHeap, fmt};
+
+use ndarray::{parallel::prelude::*, Array2};
+
+use super::{cell::CellValue, Landscape, World};
+use crate::data_structures::move_model::MoveModel;
+
+/// Statistics of the values of a world, used to normalise results across worlds
+#[derive(Clone, Debug, PartialEq)]
+pub struct WorldStats<T: CellValue = i64> {
+    /// Number of locations per bin, where the bins evenly span the bounds of the world. For integer worlds with the
+    /// default bounds, every value has its own bin
+    pub histogram: Vec<usize>,
+    pub mean: f64,
+    /// Population standard deviation
+    pub std: f64,
+    /// Fraction of locations that hold any value
+    pub non_zero_fraction: f64,
+    /// Sparsity the world was generated with, if any
+    pub requested_sparsity: Option<f64>,
+    pub total_value: T::Sum,
+    /// Most valuable locations as (y, x, value), from the most valuable down
+    pub top: Vec<(i64, i64, T)>,
+}
+
+impl<T: CellValue> WorldStats<T> {
+    pub const HISTOGRAM_BINS: usize = 101;
+
+    /// Actual sparsity of the world, being the fraction of empty locations
+    pub fn sparsity(&self) -> f64 {
+        1.0 - self.non_zero_fraction
+    }
+}
+
+impl<T: CellValue> fmt::Display for WorldStats<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "total={}, mean={:.2}, std={:.2}, sparsity={:.3}",
+            self.total_value,
+            self.mean,
+            self.std,
+            self.sparsity()
+        )?;
+        if let Some(requested) = self.requested_sparsity {
+            write!(f, " (requested {:.3})", requested)?;
+        }
+        if let Some((y, x, value)) = self.top.first() {
+            write!(f, ", max={} at y={}, x={}", value, y, x)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: CellValue> World<T> {
+    /// Computes the statistics of the world, including its k most valuable locations. The histogram, the fraction of
+    /// non-zero locations and the most valuable locations are computed in parallel. Sums are computed on a single
+    /// thread, so that their rounding does not depend on the number of threads
+    pub fn stats(&self, top_k: usize) -> WorldStats<T> {
+        let n = self.information.len().max(1) as f64;
+        let total_value: T::Sum = self.information.iter().map(|&v| T::Sum::from(v)).sum();
+        let mean = self.information.iter().map(|v| v.to_f64()).sum::<f64>() / n;
+        let variance = self
+            .information
+            .iter()
+            .map(|&v| (v.to_f64() - mean).powi(2))
+            .sum::<f64>()
+            / n;
+
+        let bins = WorldStats::<T>::HISTOGRAM_BINS;
+        let (lower, upper) = (self.lower_bound.to_f64(), self.upper_bound.to_f64());
+        let histogram = self
+            .information
+            .par_iter()
+            .fold(
+                || vec![0; bins],
+                |mut histogram, &v| {
+                    let value_norm =
+                        ((v.to_f64() - lower) / (upper - lower).max(f64::EPSILON)).clamp(0.0, 1.0);
+                    histogram[(value_norm * (bins - 1) as f64).round() as usize] += 1;
+                    histogram
+                },
+            )
+            .reduce(
+                || vec![0; bins],
+                |a, b| a.iter().zip(b.iter()).map(|(a, b)| a + b).collect(),
+            );
+
+        // Ties are broken by location, so that the top locations are deterministic
+        let mut top: Vec<(i64, i64, T)> = self
+            .information
+            .indexed_iter()
+            .map(|((y, x), &v)| (y as i64, x as i64, v))
+            .collect();
+        let order = |a: &(i64, i64, T), b: &(i64, i64, T)| {
+            b.2.partial_cmp(&a.2)
+                .unwrap_or(Ordering::Equal)
+                .then((a.0, a.1).cmp(&(b.0, b.1)))
+        };
+        // Only the top k locations are sorted, after selecting them in linear time
+        if top_k < top.len() {
+            top.select_nth_unstable_by(top_k, order);
+            top.truncate(top_k);
+        }
+        top.sort_by(order);
+
+        WorldStats {
+            histogram,
+            mean,
+            std: variance.sqrt(),
+            non_zero_fraction: self
+                .information
+                .par_iter()
+                .filter(|&&v| v != T::default())
+                .count() as f64
+                / n,
+            requested_sparsity: self.sparsity,
+            total_value,
+            top,
+        }
+    }
+
+    /// Returns the total value of all locations that can be reached from any of the starts (y, x, n_steps) within
+    /// the steps of that start, taking the steps of the move model around obstacles. Locations within reach of several
+    /// starts are counted once. This is an upper bound on the joint score of any paths from these starts
+    pub fn reachable_value(&self, move_model: &MoveModel, starts: &[(i64, i64, u64)]) -> T::Sum {
+        // Locations are settled with the most steps left of any start, so that they are expanded only once
+        let mut settled: Array2<bool> = Array2::from_elem((self.height, self.width), false);
+        let mut queue: BinaryHeap<(u64, i64, i64)> = starts
+            .iter()
+            .filter(|&&(y, x, _)| self.is_passable(y, x))
+            .map(|&(y, x, n_steps)| {
+                let (y, x) = self.wrap(y, x);
+                (n_steps, y, x)
+            })
+            .collect();
+
+        let mut value = T::Sum::default();
+        while let Some((steps_left, y, x)) = queue.pop() {
+            if settled[[y as usize, x as usize]] {
+                continue;
+            }
+            settled[[y as usize, x as usize]] = true;
+            value += self.information[[y as usize, x as usize]].into();
+            if steps_left == 0 {
+                continue;
+            }
+            for &(dy, dx) in move_model.moves(y) {
+                if !self.is_passable(y + dy, x + dx) {
+                    continue;
+                }
+                let (ny, nx) = self.wrap(y + dy, x + dx);
+                if !settled[[ny as usize, nx as usize]] {
+                    queue.push((steps_left - 1, ny, nx));
+                }
+            }
+        }
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reachable_value_counts_overlapping_reach_once() {
+        let mut world: World = World::new(1, 10);
+        for x in 0..10 {
+            world.set(0, x, 1);
+        }
+        let model = MoveModel::FourConnected;
+        assert_eq!(world.reachable_value(&model, &[(0, 2, 2)]), 5);
+        assert_eq!(world.reachable_value(&model, &[(0, 2, 2), (0, 3, 2)]), 6);
+        // The start with the most steps reaches beyond a start with fewer steps next to it
+        assert_eq!(world.reachable_value(&model, &[(0, 5, 0), (0, 4, 3)]), 7);
+        assert_eq!(world.reachable_value(&model, &[(0, 5, 9)]), 10);
+        assert_eq!(world.reachable_value(&model, &[(-1, 5, 9)]), 0);
+
+        let stats = world.stats(0);
+        assert_eq!(
+            (stats.total_value, stats.sparsity(), stats.std),
+            (10, 0.0, 0.0)
+        );
+    }
+}
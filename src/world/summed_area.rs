@@ -0,0 +1,76 @@
+use ndarray::{Array2, ArrayView2};
+
+use super::cell::CellSum;
+
+/// A summed-area table (integral image) of the values of a world. Every entry holds the sum of all values above and
+/// to the left of it, so that the sum of any rectangular block can be computed from just four entries.
+///
+/// Rebuilding the table costs O(area), which is too expensive to do on every visit. Instead, changes to single
+/// locations are kept as a list of corrections on top of the table, and the table is only rebuilt once that list
+/// grows too long
+#[derive(Clone, Debug)]
+pub struct SummedAreaTable<S: CellSum> {
+    /// Of shape (height + 1, width + 1). The first row and column are zero
+    table: Array2<S>,
+    /// Changes made since the table was built, as (y, x, delta)
+    corrections: Vec<(i64, i64, S)>,
+    max_corrections: usize,
+}
+
+impl<S: CellSum> SummedAreaTable<S> {
+    pub fn new<T: Copy + Into<S>>(information: ArrayView2<T>) -> SummedAreaTable<S> {
+        let (height, width) = information.dim();
+        let mut table = Array2::from_elem((height + 1, width + 1), S::default());
+        for y in 0..height {
+            for x in 0..width {
+                table[[y + 1, x + 1]] =
+                    information[[y, x]].into() + table[[y, x + 1]] + table[[y + 1, x]]
+                        - table[[y, x]];
+            }
+        }
+
+        // Balances the cost of rebuilding against the cost of applying the corrections to every block sum
+        let max_corrections = usize::max(16, ((height * width) as f64).sqrt() as usize / 8);
+
+        SummedAreaTable {
+            table,
+            corrections: Vec::new(),
+            max_corrections,
+        }
+    }
+
+    /// Records that the value of a location changed by delta. Returns false once the table should be rebuilt
+    pub fn correct(&mut self, y: i64, x: i64, delta: S) -> bool {
+        if delta != S::default() {
+            self.corrections.push((y, x, delta));
+        }
+        self.corrections.len() <= self.max_corrections
+    }
+
+    /// Returns the sum of the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1. The block is clamped
+    /// to the world, so that blocks partially or completely outside of it are allowed. Takes O(max_corrections), being
+    /// O(sqrt(area)), as every pending correction is checked
+    pub fn sum(&self, y0: i64, y1: i64, x0: i64, x1: i64) -> S {
+        let (rows, cols) = (self.table.nrows() as i64 - 1, self.table.ncols() as i64 - 1);
+        let (y0, y1) = (y0.clamp(0, rows), y1.clamp(0, rows));
+        let (x0, x1) = (x0.clamp(0, cols), x1.clamp(0, cols));
+        if y1 <= y0 || x1 <= x0 {
+            return S::default();
+        }
+
+        let [y0, y1, x0, x1] = [y0, y1, x0, x1].map(|v| v as usize);
+        let sum = self.table[[y1, x1]] - self.table[[y0, x1]] - self.table[[y1, x0]]
+            + self.table[[y0, x0]];
+
+        let correction: S = self
+            .corrections
+            .iter()
+            .filter(|&&(y, x, _)| {
+                (y0 as i64..y1 as i64).contains(&y) && (x0 as i64..x1 as i64).contains(&x)
+            })
+            .map(|&(_, _, delta)| delta)
+            .sum();
+
+        sum + correction
+    }
+}
@@ -0,0 +1,34 @@
+use std::collections::HashMap;
+
+/// Locations bucketed by the tile of SIZE by SIZE locations they lie in, so that the locations within a block are
+/// found by looking only at the tiles the block overlaps
+#[derive(Clone, Debug, Default)]
+pub struct TileIndex {
+    tiles: HashMap<(i64, i64), Vec<(i64, i64)>>,
+}
+
+impl TileIndex {
+    pub const SIZE: i64 = 64;
+
+    /// Adds a location of the world, which must not have been added before
+    pub fn insert(&mut self, y: i64, x: i64) {
+        let tile = (y / Self::SIZE, x / Self::SIZE);
+        self.tiles.entry(tile).or_default().push((y, x));
+    }
+
+    /// Returns the locations within the block of rows y0..y1 and columns x0..x1, exclusive of y1 and x1. The block has
+    /// to lie within the world
+    pub fn in_block(
+        &self,
+        (y0, y1, x0, x1): (i64, i64, i64, i64),
+    ) -> impl Iterator<Item = (i64, i64)> + '_ {
+        (y0 / Self::SIZE..=(y1 - 1) / Self::SIZE)
+            .flat_map(move |tile_y| {
+                (x0 / Self::SIZE..=(x1 - 1) / Self::SIZE).map(move |tile_x| (tile_y, tile_x))
+            })
+            .filter_map(|tile| self.tiles.get(&tile))
+            .flatten()
+            .copied()
+            .filter(move |&(y, x)| (y0..y1).contains(&y) && (x0..x1).contains(&x))
+    }
+}
@@ -0,0 +1,165 @@
+use std::{collections::HashMap, sync::RwLock};
+
+use color_eyre::eyre::Result;
+use ndarray::Array2;
+use noise::NoiseFn;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use super::{
+    cell::CellValue, generator::NoiseConfig, noise_to_value, rescale, sparsity_cutoff, Landscape,
+    World,
+};
+
+/// A world without bounds, filled with noise. The world is divided into square tiles, which are only generated
+/// once a location within them is looked at or visited. Memory usage therefore only grows with the part of the world
+/// that is actually explored. Since the noise is seeded, a tile that is generated twice will have the same values
+pub struct TiledWorld {
+    /// Number of locations spanned by a single unit of noise. A TiledWorld with a scale of N looks like a perlin World
+    /// with a grid size of N
+    scale: f64,
+    config: NoiseConfig,
+    noise_seed: u32,
+    /// Sparsity cutoff and maximum value, see `sparsity_cutoff`. None if no sparsity is applied
+    cutoff: Option<(f64, f64)>,
+    tiles: RwLock<HashMap<(i64, i64), Array2<i64>>>,
+    seed: u64,
+}
+
+impl TiledWorld {
+    pub const TILE_SIZE: i64 = 64;
+    /// Number of samples taken along each axis to estimate the statistics of the noise
+    const SAMPLE_SIZE: usize = 128;
+    /// Number of units of noise along each axis over which the samples are spread
+    const SAMPLE_SPAN: f64 = 8.0;
+    /// Values lie within the default bounds of a World
+    const LOWER_BOUND: f64 = i64::LOWER as f64;
+    const UPPER_BOUND: f64 = i64::UPPER as f64;
+
+    /// Creates a new unbounded world with the configured noise, generated from the given seed. Fails if a sparsity is
+    /// given but the sampled noise has no spread
+    /// sparsity: 0.0 - 1.0
+    pub fn noise(scale: f64, sparsity: f64, config: NoiseConfig, seed: u64) -> Result<TiledWorld> {
+        let noise_seed = StdRng::seed_from_u64(seed).random_range(0..u32::MAX);
+        let noise = config.build(noise_seed);
+
+        // The world is infinite, so the statistics needed for the sparsity are estimated from a sample of the noise
+        let cutoff = if sparsity <= 0.0 {
+            None
+        } else {
+            let step = Self::SAMPLE_SPAN / Self::SAMPLE_SIZE as f64;
+            let samples = (0..Self::SAMPLE_SIZE * Self::SAMPLE_SIZE).map(|i| {
+                let (y, x) = (i / Self::SAMPLE_SIZE, i % Self::SAMPLE_SIZE);
+                Self::value_at(&noise, y as f64 * step, x as f64 * step).to_f64()
+            });
+            Some(sparsity_cutoff(samples, sparsity, |v| {
+                i64::from_f64(v).to_f64()
+            })?)
+        };
+
+        Ok(TiledWorld {
+            scale,
+            config,
+            noise_seed,
+            cutoff,
+            tiles: RwLock::new(HashMap::new()),
+            seed,
+        })
+    }
+
+    /// Copies the block of height by width locations starting at (y0, x0) into a dense, finite world, generating the
+    /// tiles the block covers. The world takes on the seed of this world
+    pub fn window(&self, y0: i64, x0: i64, height: usize, width: usize) -> World {
+        let mut world = World::new(height, width);
+        world.information = Array2::from_shape_fn((height, width), |(y, x)| {
+            self.at(y0 + y as i64, x0 + x as i64).unwrap()
+        });
+        world.seed = Some(self.seed);
+        world
+    }
+
+    /// Returns the tile a location is in, and the location within that tile
+    fn locate(y: i64, x: i64) -> ((i64, i64), (usize, usize)) {
+        let tile = (y.div_euclid(Self::TILE_SIZE), x.div_euclid(Self::TILE_SIZE));
+        let offset = (
+            y.rem_euclid(Self::TILE_SIZE) as usize,
+            x.rem_euclid(Self::TILE_SIZE) as usize,
+        );
+        (tile, offset)
+    }
+
+    /// Generates the values of the tile with the given tile coordinates. The noise function is created anew for every
+    /// tile, since not all noise functions can be shared between threads
+    fn generate_tile(&self, (tile_y, tile_x): (i64, i64)) -> Array2<i64> {
+        let noise = self.config.build(self.noise_seed);
+        let size = Self::TILE_SIZE as usize;
+        Array2::from_shape_fn((size, size), |(dy, dx)| {
+            let y = tile_y * Self::TILE_SIZE + dy as i64;
+            let x = tile_x * Self::TILE_SIZE + dx as i64;
+            let value = Self::value_at(&noise, y as f64 / self.scale, x as f64 / self.scale);
+            match self.cutoff {
+                Some((zscore, max_value)) => i64::from_f64(rescale(
+                    value.to_f64(),
+                    zscore,
+                    max_value,
+                    Self::LOWER_BOUND,
+                    Self::UPPER_BOUND,
+                )),
+                None => value,
+            }
+        })
+    }
+
+    /// Returns the value of the noise at the given coordinates, in units of noise, before sparsity is applied
+    fn value_at(noise: &dyn NoiseFn<f64, 2>, y: f64, x: f64) -> i64 {
+        i64::from_f64(noise_to_value(
+            noise.get([x, y]),
+            Self::LOWER_BOUND,
+            Self::UPPER_BOUND,
+        ))
+    }
+}
+
+impl Clone for TiledWorld {
+    fn clone(&self) -> Self {
+        TiledWorld {
+            scale: self.scale,
+            config: self.config.clone(),
+            noise_seed: self.noise_seed,
+            cutoff: self.cutoff,
+            tiles: RwLock::new(self.tiles.read().unwrap().clone()),
+            seed: self.seed,
+        }
+    }
+}
+
+impl Landscape for TiledWorld {
+    type Value = i64;
+
+    fn at(&self, y: i64, x: i64) -> Option<i64> {
+        let (tile, offset) = Self::locate(y, x);
+
+        if let Some(values) = self.tiles.read().unwrap().get(&tile) {
+            return Some(values[offset]);
+        }
+
+        // The tile has not been generated yet
+        let mut tiles = self.tiles.write().unwrap();
+        let values = tiles
+            .entry(tile)
+            .or_insert_with(|| self.generate_tile(tile));
+        Some(values[offset])
+    }
+
+    fn set(&mut self, y: i64, x: i64, value: i64) {
+        let (tile, offset) = Self::locate(y, x);
+        if !self.tiles.get_mut().unwrap().contains_key(&tile) {
+            let values = self.generate_tile(tile);
+            self.tiles.get_mut().unwrap().insert(tile, values);
+        }
+        self.tiles.get_mut().unwrap().get_mut(&tile).unwrap()[offset] = value;
+    }
+
+    fn in_world(&self, _y: i64, _x: i64) -> bool {
+        true
+    }
+}